@@ -0,0 +1,361 @@
+//! Builds & signs market deploys from the command line, writing the node's deploy JSON so it can
+//! be sent with `casper-client send-deploy`. Leave out `--secret-key` (passing `--public-key`
+//! instead) to write an unsigned deploy & sign it later, on another machine, with `sign`.
+
+use std::{
+    fmt, fs, io, process,
+    path::{Path, PathBuf}
+};
+
+use casper_types::{ContractHash, Key, PublicKey, SecretKey, U512};
+use clap::{Args, Parser, Subcommand};
+use market_client::{install_market, upgrade_market, Deploy, DeployParams, MarketClient};
+
+#[derive(Debug)]
+enum Error {
+    Client(market_client::Error),
+    Io(io::Error),
+    Json(serde_json::Error),
+    Key(String),
+    KeyMismatch,
+    MissingAccount
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Client(error) => write!(f, "{}", error),
+            Error::Io(error) => write!(f, "io error: {}", error),
+            Error::Json(error) => write!(f, "invalid json: {}", error),
+            Error::Key(error) => write!(f, "invalid key file: {}", error),
+            Error::KeyMismatch => write!(f, "--public-key isn't the public key of --secret-key"),
+            Error::MissingAccount => write!(f, "pass --secret-key to sign or --public-key for an unsigned deploy"),
+        }
+    }
+}
+
+impl From<market_client::Error> for Error {
+    fn from(error: market_client::Error) -> Self {
+        Error::Client(error)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
+
+#[derive(Parser)]
+#[clap(name = "market-cli", about = "Build & sign deploys for the nft market contract")]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List a token for sale (create_listing)
+    List {
+        #[clap(flatten)]
+        deploy: DeployOptions,
+        #[clap(flatten)]
+        token: TokenOptions,
+        /// Price in motes
+        #[clap(long, parse(try_from_str = parse_motes))]
+        price: U512,
+        /// Move the token to the market until the listing closes (create_custody_listing)
+        #[clap(long)]
+        custody: bool
+    },
+    /// Cancel a listing (cancel_listing)
+    Cancel {
+        #[clap(flatten)]
+        deploy: DeployOptions,
+        #[clap(flatten)]
+        token: TokenOptions
+    },
+    /// Buy a listed token through the payment wasm (buy_listing)
+    Buy {
+        #[clap(flatten)]
+        deploy: DeployOptions,
+        #[clap(flatten)]
+        token: TokenOptions,
+        #[clap(flatten)]
+        payment: PaymentOptions
+    },
+    /// Buy a collection's cheapest listing through the payment wasm (buy_floor)
+    BuyFloor {
+        #[clap(flatten)]
+        deploy: DeployOptions,
+        #[clap(long, parse(try_from_str = parse_contract_hash))]
+        market_contract_hash: ContractHash,
+        #[clap(long, parse(try_from_str = parse_contract_hash))]
+        token_contract_hash: ContractHash,
+        #[clap(flatten)]
+        payment: PaymentOptions
+    },
+    /// Make an offer through the payment wasm (make_offer)
+    Offer {
+        #[clap(flatten)]
+        deploy: DeployOptions,
+        #[clap(flatten)]
+        token: TokenOptions,
+        #[clap(flatten)]
+        payment: PaymentOptions
+    },
+    /// Withdraw an offer (withdraw_offer)
+    Withdraw {
+        #[clap(flatten)]
+        deploy: DeployOptions,
+        #[clap(flatten)]
+        token: TokenOptions
+    },
+    /// Accept an offer (accept_offer)
+    Accept {
+        #[clap(flatten)]
+        deploy: DeployOptions,
+        #[clap(flatten)]
+        token: TokenOptions,
+        /// The bidder's formatted key, e.g. account-hash-...
+        #[clap(long, parse(try_from_str = parse_key))]
+        buyer: Key
+    },
+    /// Claim sale proceeds & refunds to the account's main purse (claim)
+    Claim {
+        #[clap(flatten)]
+        deploy: DeployOptions,
+        #[clap(long, parse(try_from_str = parse_contract_hash))]
+        market_contract_hash: ContractHash
+    },
+    /// Install the market contract
+    Install {
+        #[clap(flatten)]
+        deploy: DeployOptions,
+        #[clap(long)]
+        market_wasm: PathBuf,
+        #[clap(long)]
+        max_offers_per_token: u32,
+        /// Smallest offer in motes
+        #[clap(long, parse(try_from_str = parse_motes))]
+        min_offer_amount: U512
+    },
+    /// Add a new version of the market contract, keeping its storage & config
+    Upgrade {
+        #[clap(flatten)]
+        deploy: DeployOptions,
+        #[clap(long)]
+        market_wasm: PathBuf
+    },
+    /// Add an approval to a deploy file
+    Sign {
+        #[clap(long)]
+        input: PathBuf,
+        #[clap(long)]
+        secret_key: PathBuf,
+        #[clap(long)]
+        output: Option<PathBuf>
+    },
+}
+
+#[derive(Args)]
+struct DeployOptions {
+    #[clap(long)]
+    chain_name: String,
+    /// PEM secret key to sign with, also sets the account
+    #[clap(long)]
+    secret_key: Option<PathBuf>,
+    /// PEM public key of the account, for deploys signed later with `sign`. Has to match
+    /// `--secret-key` when both are given
+    #[clap(long)]
+    public_key: Option<PathBuf>,
+    /// Standard payment in motes
+    #[clap(long, parse(try_from_str = parse_motes))]
+    payment_amount: U512,
+    #[clap(long, default_value = "30m", parse(try_from_str = humantime::parse_duration))]
+    ttl: std::time::Duration,
+    #[clap(long, default_value = "1")]
+    gas_price: u64,
+    /// Where to write the deploy JSON, stdout when left out
+    #[clap(long)]
+    output: Option<PathBuf>
+}
+
+#[derive(Args)]
+struct TokenOptions {
+    #[clap(long, parse(try_from_str = parse_contract_hash))]
+    market_contract_hash: ContractHash,
+    #[clap(long, parse(try_from_str = parse_contract_hash))]
+    token_contract_hash: ContractHash,
+    #[clap(long)]
+    token_id: String
+}
+
+#[derive(Args)]
+struct PaymentOptions {
+    #[clap(long)]
+    payment_wasm: PathBuf,
+    /// Motes moved into the purse handed to the market, the most `buy-floor` will pay
+    #[clap(long, parse(try_from_str = parse_motes))]
+    amount: U512
+}
+
+impl PaymentOptions {
+    fn client(&self, market_contract_hash: ContractHash) -> Result<MarketClient, Error> {
+        Ok(MarketClient::new(market_contract_hash).with_payment_wasm(fs::read(&self.payment_wasm)?))
+    }
+}
+
+fn parse_motes(value: &str) -> Result<U512, String> {
+    U512::from_dec_str(value).map_err(|_| format!("invalid amount of motes: {}", value))
+}
+
+// takes both the `hash-` named key form & the `contract-` form the market args use
+fn parse_contract_hash(value: &str) -> Result<ContractHash, String> {
+    let hex = value.trim_start_matches("hash-").trim_start_matches("contract-");
+    ContractHash::from_formatted_str(&format!("contract-{}", hex))
+        .map_err(|_| format!("invalid contract hash: {}", value))
+}
+
+fn parse_key(value: &str) -> Result<Key, String> {
+    Key::from_formatted_str(value).map_err(|_| format!("invalid key: {}", value))
+}
+
+fn read_secret_key(path: &Path) -> Result<SecretKey, Error> {
+    SecretKey::from_file(path).map_err(|error| Error::Key(error.to_string()))
+}
+
+struct Signer {
+    params: DeployParams,
+    secret_key: Option<SecretKey>,
+    output: Option<PathBuf>
+}
+
+impl Signer {
+    fn new(options: DeployOptions) -> Result<Signer, Error> {
+        let secret_key = options.secret_key.as_deref().map(read_secret_key).transpose()?;
+        let public_key = options.public_key.as_deref()
+            .map(|path| PublicKey::from_file(path).map_err(|error| Error::Key(error.to_string())))
+            .transpose()?;
+        // the account has to be the signer, the node would only reject the deploy later
+        let account = match (public_key, &secret_key) {
+            (Some(public_key), Some(secret_key)) if public_key != PublicKey::from(secret_key) => {
+                return Err(Error::KeyMismatch)
+            },
+            (Some(public_key), _) => public_key,
+            (None, Some(secret_key)) => PublicKey::from(secret_key),
+            (None, None) => return Err(Error::MissingAccount)
+        };
+
+        let mut params = DeployParams::new(&options.chain_name, account, options.payment_amount);
+        params.ttl = options.ttl.as_millis() as u64;
+        params.gas_price = options.gas_price;
+        Ok(Signer { params, secret_key, output: options.output })
+    }
+
+    fn finish(self, mut deploy: Deploy) -> Result<(), Error> {
+        if let Some(secret_key) = &self.secret_key {
+            deploy.sign(secret_key);
+        }
+        write_deploy(&deploy, self.output)
+    }
+}
+
+fn write_deploy(deploy: &Deploy, output: Option<PathBuf>) -> Result<(), Error> {
+    let json = serde_json::to_string_pretty(&deploy.to_json()?)?;
+    match output {
+        Some(path) => {
+            fs::write(&path, json)?;
+            eprintln!("deploy {} written to {}", hex::encode(deploy.hash), path.display());
+        },
+        None => println!("{}", json)
+    }
+    Ok(())
+}
+
+fn run(command: Command) -> Result<(), Error> {
+    match command {
+        Command::List { deploy, token, price, custody } => {
+            let signer = Signer::new(deploy)?;
+            let client = MarketClient::new(token.market_contract_hash);
+            let deploy = if custody {
+                client.create_custody_listing(&signer.params, &token.token_contract_hash, &token.token_id, price)?
+            } else {
+                client.create_listing(&signer.params, &token.token_contract_hash, &token.token_id, price)?
+            };
+            signer.finish(deploy)
+        },
+        Command::Cancel { deploy, token } => {
+            let signer = Signer::new(deploy)?;
+            let deploy = MarketClient::new(token.market_contract_hash)
+                .cancel_listing(&signer.params, &token.token_contract_hash, &token.token_id)?;
+            signer.finish(deploy)
+        },
+        Command::Buy { deploy, token, payment } => {
+            let signer = Signer::new(deploy)?;
+            let deploy = payment.client(token.market_contract_hash)?
+                .buy_listing(&signer.params, &token.token_contract_hash, &token.token_id, payment.amount)?;
+            signer.finish(deploy)
+        },
+        Command::BuyFloor { deploy, market_contract_hash, token_contract_hash, payment } => {
+            let signer = Signer::new(deploy)?;
+            let deploy = payment.client(market_contract_hash)?
+                .buy_floor(&signer.params, &token_contract_hash, payment.amount)?;
+            signer.finish(deploy)
+        },
+        Command::Offer { deploy, token, payment } => {
+            let signer = Signer::new(deploy)?;
+            let deploy = payment.client(token.market_contract_hash)?
+                .make_offer(&signer.params, &token.token_contract_hash, &token.token_id, payment.amount)?;
+            signer.finish(deploy)
+        },
+        Command::Withdraw { deploy, token } => {
+            let signer = Signer::new(deploy)?;
+            let deploy = MarketClient::new(token.market_contract_hash)
+                .withdraw_offer(&signer.params, &token.token_contract_hash, &token.token_id)?;
+            signer.finish(deploy)
+        },
+        Command::Accept { deploy, token, buyer } => {
+            let signer = Signer::new(deploy)?;
+            let deploy = MarketClient::new(token.market_contract_hash)
+                .accept_offer(&signer.params, &token.token_contract_hash, &token.token_id, &buyer)?;
+            signer.finish(deploy)
+        },
+        Command::Claim { deploy, market_contract_hash } => {
+            let signer = Signer::new(deploy)?;
+            let deploy = MarketClient::new(market_contract_hash).claim(&signer.params)?;
+            signer.finish(deploy)
+        },
+        Command::Install { deploy, market_wasm, max_offers_per_token, min_offer_amount } => {
+            let signer = Signer::new(deploy)?;
+            let deploy = install_market(&signer.params, fs::read(market_wasm)?, max_offers_per_token, min_offer_amount)?;
+            signer.finish(deploy)
+        },
+        Command::Upgrade { deploy, market_wasm } => {
+            let signer = Signer::new(deploy)?;
+            let deploy = upgrade_market(&signer.params, fs::read(market_wasm)?)?;
+            signer.finish(deploy)
+        },
+        // refuses files whose hashes or existing approvals don't check out
+        Command::Sign { input, secret_key, output } => {
+            let json = serde_json::from_str(&fs::read_to_string(input)?)?;
+            let mut deploy = Deploy::from_json(&json)?;
+            deploy.verify()?;
+            deploy.sign(&read_secret_key(&secret_key)?);
+            write_deploy(&deploy, output)
+        },
+    }
+}
+
+fn main() {
+    if let Err(error) = run(Cli::parse().command) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,152 @@
+use std::{fs, path::Path, process::Command};
+
+use casper_types::{PublicKey, SecretKey};
+use market_client::Deploy;
+use serde_json::Value;
+use tempfile::TempDir;
+
+const MARKET_CONTRACT_HASH: &str = "hash-cccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc";
+const TOKEN_CONTRACT_HASH: &str = "contract-aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+
+fn market_cli(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_market-cli")).args(args).output().unwrap()
+}
+
+fn write_keys(dir: &Path) -> PublicKey {
+    let secret_key = SecretKey::ed25519_from_bytes([1; 32]).unwrap();
+    let public_key = PublicKey::from(&secret_key);
+    secret_key.to_file(dir.join("secret_key.pem")).unwrap();
+    public_key.to_file(dir.join("public_key.pem")).unwrap();
+    public_key
+}
+
+fn read_deploy(path: &Path) -> Deploy {
+    let json: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+    Deploy::from_json(&json).unwrap()
+}
+
+fn path(dir: &TempDir, name: &str) -> String {
+    dir.path().join(name).to_str().unwrap().to_string()
+}
+
+#[test]
+fn should_write_a_signed_listing_deploy() {
+    let dir = TempDir::new().unwrap();
+    let public_key = write_keys(dir.path());
+
+    let output = market_cli(&[
+        "list",
+        "--chain-name", "casper-net-1",
+        "--secret-key", &path(&dir, "secret_key.pem"),
+        "--payment-amount", "3000000000",
+        "--market-contract-hash", MARKET_CONTRACT_HASH,
+        "--token-contract-hash", TOKEN_CONTRACT_HASH,
+        "--token-id", "1",
+        "--price", "100000000000",
+        "--output", &path(&dir, "list.json")
+    ]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let deploy = read_deploy(&dir.path().join("list.json"));
+    deploy.verify().unwrap();
+    assert_eq!(deploy.header.account, public_key);
+    assert_eq!(deploy.approvals.len(), 1);
+}
+
+#[test]
+fn should_reject_a_public_key_that_isnt_the_signers() {
+    let dir = TempDir::new().unwrap();
+    write_keys(dir.path());
+    let other = PublicKey::from(&SecretKey::ed25519_from_bytes([2; 32]).unwrap());
+    other.to_file(dir.path().join("other_public_key.pem")).unwrap();
+
+    let claim = |public_key: &str| market_cli(&[
+        "claim",
+        "--chain-name", "casper-net-1",
+        "--secret-key", &path(&dir, "secret_key.pem"),
+        "--public-key", &path(&dir, public_key),
+        "--payment-amount", "3000000000",
+        "--market-contract-hash", MARKET_CONTRACT_HASH,
+        "--output", &path(&dir, "claim.json")
+    ]);
+
+    let output = claim("other_public_key.pem");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--public-key isn't the public key of --secret-key"));
+    assert!(!dir.path().join("claim.json").exists());
+
+    let output = claim("public_key.pem");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    read_deploy(&dir.path().join("claim.json")).verify().unwrap();
+}
+
+#[test]
+fn should_sign_an_unsigned_deploy_offline() {
+    let dir = TempDir::new().unwrap();
+    write_keys(dir.path());
+
+    let output = market_cli(&[
+        "claim",
+        "--chain-name", "casper-net-1",
+        "--public-key", &path(&dir, "public_key.pem"),
+        "--payment-amount", "3000000000",
+        "--market-contract-hash", MARKET_CONTRACT_HASH,
+        "--output", &path(&dir, "unsigned.json")
+    ]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(read_deploy(&dir.path().join("unsigned.json")).approvals.is_empty());
+
+    let output = market_cli(&[
+        "sign",
+        "--input", &path(&dir, "unsigned.json"),
+        "--secret-key", &path(&dir, "secret_key.pem"),
+        "--output", &path(&dir, "signed.json")
+    ]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let signed = read_deploy(&dir.path().join("signed.json"));
+    signed.verify().unwrap();
+    assert_eq!(signed.hash, read_deploy(&dir.path().join("unsigned.json")).hash);
+    assert_eq!(signed.approvals.len(), 1);
+}
+
+#[test]
+fn should_refuse_to_sign_a_tampered_deploy() {
+    let dir = TempDir::new().unwrap();
+    write_keys(dir.path());
+
+    let output = market_cli(&[
+        "cancel",
+        "--chain-name", "casper-net-1",
+        "--public-key", &path(&dir, "public_key.pem"),
+        "--payment-amount", "3000000000",
+        "--market-contract-hash", MARKET_CONTRACT_HASH,
+        "--token-contract-hash", TOKEN_CONTRACT_HASH,
+        "--token-id", "1",
+        "--output", &path(&dir, "unsigned.json")
+    ]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let mut json: Value = serde_json::from_str(&fs::read_to_string(dir.path().join("unsigned.json")).unwrap()).unwrap();
+    json["header"]["chain_name"] = Value::from("casper");
+    fs::write(dir.path().join("unsigned.json"), json.to_string()).unwrap();
+
+    let output = market_cli(&[
+        "sign",
+        "--input", &path(&dir, "unsigned.json"),
+        "--secret-key", &path(&dir, "secret_key.pem")
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("deploy hash"));
+}
+
+#[test]
+fn should_need_an_account_key() {
+    let output = market_cli(&[
+        "claim",
+        "--chain-name", "casper-net-1",
+        "--payment-amount", "3000000000",
+        "--market-contract-hash", MARKET_CONTRACT_HASH
+    ]);
+    assert!(!output.status.success());
+}
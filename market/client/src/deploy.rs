@@ -0,0 +1,351 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use blake2::{
+    digest::{Update, VariableOutput},
+    VarBlake2b
+};
+use casper_types::{
+    bytesrepr::{self, Bytes, ToBytes},
+    crypto, runtime_args, ContractHash, PublicKey, RuntimeArgs, SecretKey, Signature, U512};
+use serde_json::{json, Value};
+
+use crate::Error;
+
+pub const DEFAULT_TTL: u64 = 30 * 60 * 1000;
+pub const DEFAULT_GAS_PRICE: u64 = 1;
+
+const MODULE_BYTES_TAG: u8 = 0;
+const STORED_CONTRACT_BY_HASH_TAG: u8 = 1;
+
+// standard payment is empty module bytes with an `amount` arg
+const PAYMENT_AMOUNT_ARG: &str = "amount";
+
+pub type Digest = [u8; 32];
+
+pub fn blake2b<T: AsRef<[u8]>>(data: T) -> Digest {
+    let mut result = [0; 32];
+    let mut hasher = VarBlake2b::new(32).expect("32 is a valid blake2b output size");
+    hasher.update(data);
+    hasher.finalize_variable(|hash| result.copy_from_slice(hash));
+    result
+}
+
+/// Who sends a deploy & how it's paid for, shared by every deploy a client builds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeployParams {
+    pub chain_name: String,
+    pub account: PublicKey,
+    pub payment_amount: U512,
+    /// Milliseconds since the unix epoch.
+    pub timestamp: u64,
+    /// Milliseconds.
+    pub ttl: u64,
+    pub gas_price: u64
+}
+
+impl DeployParams {
+    pub fn new(chain_name: &str, account: PublicKey, payment_amount: U512) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.as_millis() as u64);
+
+        DeployParams {
+            chain_name: chain_name.to_string(),
+            account,
+            payment_amount,
+            timestamp,
+            ttl: DEFAULT_TTL,
+            gas_price: DEFAULT_GAS_PRICE
+        }
+    }
+}
+
+/// The subset of the node's executable deploy items the market needs: wasm to run as session
+/// code (or empty module bytes for standard payment) & calls to a stored contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutableDeployItem {
+    ModuleBytes {
+        module_bytes: Bytes,
+        args: RuntimeArgs
+    },
+    StoredContractByHash {
+        hash: ContractHash,
+        entry_point: String,
+        args: RuntimeArgs
+    },
+}
+
+impl ExecutableDeployItem {
+    pub fn standard_payment(amount: U512) -> Self {
+        ExecutableDeployItem::ModuleBytes {
+            module_bytes: Bytes::new(),
+            args: runtime_args! { PAYMENT_AMOUNT_ARG => amount }
+        }
+    }
+
+    pub fn args(&self) -> &RuntimeArgs {
+        match self {
+            ExecutableDeployItem::ModuleBytes { args, .. } => args,
+            ExecutableDeployItem::StoredContractByHash { args, .. } => args,
+        }
+    }
+
+    pub fn to_json(&self) -> Result<Value, Error> {
+        let json = match self {
+            ExecutableDeployItem::ModuleBytes { module_bytes, args } => json!({
+                "ModuleBytes": {
+                    "module_bytes": hex::encode(&module_bytes[..]),
+                    "args": serde_json::to_value(args)?
+                }
+            }),
+            ExecutableDeployItem::StoredContractByHash { hash, entry_point, args } => json!({
+                "StoredContractByHash": {
+                    "hash": hex::encode(hash.value()),
+                    "entry_point": entry_point,
+                    "args": serde_json::to_value(args)?
+                }
+            }),
+        };
+        Ok(json)
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self, Error> {
+        if let Some(item) = value.get("ModuleBytes") {
+            let module_bytes = hex::decode(json_str(item, "module_bytes", "ModuleBytes.module_bytes")?)?;
+            return Ok(ExecutableDeployItem::ModuleBytes {
+                module_bytes: Bytes::from(module_bytes),
+                args: json_args(item)?
+            });
+        }
+        if let Some(item) = value.get("StoredContractByHash") {
+            return Ok(ExecutableDeployItem::StoredContractByHash {
+                hash: ContractHash::new(json_digest(item, "hash", "StoredContractByHash.hash")?),
+                entry_point: json_str(item, "entry_point", "StoredContractByHash.entry_point")?.to_string(),
+                args: json_args(item)?
+            });
+        }
+        Err(Error::UnsupportedDeployItem(value.to_string()))
+    }
+}
+
+impl ToBytes for ExecutableDeployItem {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut bytes = Vec::new();
+        match self {
+            ExecutableDeployItem::ModuleBytes { module_bytes, args } => {
+                bytes.push(MODULE_BYTES_TAG);
+                bytes.append(&mut module_bytes.to_bytes()?);
+                bytes.append(&mut args.to_bytes()?);
+            },
+            ExecutableDeployItem::StoredContractByHash { hash, entry_point, args } => {
+                bytes.push(STORED_CONTRACT_BY_HASH_TAG);
+                bytes.append(&mut hash.to_bytes()?);
+                bytes.append(&mut entry_point.to_bytes()?);
+                bytes.append(&mut args.to_bytes()?);
+            },
+        }
+        Ok(bytes)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.to_bytes().map(|bytes| bytes.len()).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeployHeader {
+    pub account: PublicKey,
+    pub timestamp: u64,
+    pub ttl: u64,
+    pub gas_price: u64,
+    pub body_hash: Digest,
+    pub dependencies: Vec<Digest>,
+    pub chain_name: String
+}
+
+impl DeployHeader {
+    pub fn to_json(&self) -> Result<Value, Error> {
+        let timestamp = UNIX_EPOCH + Duration::from_millis(self.timestamp);
+        let dependencies: Vec<String> = self.dependencies.iter().map(hex::encode).collect();
+
+        Ok(json!({
+            "account": serde_json::to_value(&self.account)?,
+            "timestamp": humantime::format_rfc3339_millis(timestamp).to_string(),
+            "ttl": humantime::format_duration(Duration::from_millis(self.ttl)).to_string(),
+            "gas_price": self.gas_price,
+            "body_hash": hex::encode(self.body_hash),
+            "dependencies": dependencies,
+            "chain_name": self.chain_name
+        }))
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self, Error> {
+        let timestamp = json_str(value, "timestamp", "header.timestamp")?;
+        let timestamp = humantime::parse_rfc3339(timestamp)
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .ok_or_else(|| Error::InvalidTime(timestamp.to_string()))?;
+        let ttl = json_str(value, "ttl", "header.ttl")?;
+        let ttl = humantime::parse_duration(ttl).map_err(|_| Error::InvalidTime(ttl.to_string()))?;
+
+        let dependencies: Result<Vec<Digest>, Error> = value["dependencies"]
+            .as_array()
+            .ok_or(Error::MissingField("header.dependencies"))?
+            .iter()
+            .map(|dependency| {
+                let mut digest = [0; 32];
+                let dependency = dependency.as_str().ok_or(Error::MissingField("header.dependencies"))?;
+                hex::decode_to_slice(dependency, &mut digest)?;
+                Ok(digest)
+            })
+            .collect();
+
+        Ok(DeployHeader {
+            account: serde_json::from_value(value["account"].clone())?,
+            timestamp: timestamp.as_millis() as u64,
+            ttl: ttl.as_millis() as u64,
+            gas_price: value["gas_price"].as_u64().ok_or(Error::MissingField("header.gas_price"))?,
+            body_hash: json_digest(value, "body_hash", "header.body_hash")?,
+            dependencies: dependencies?,
+            chain_name: json_str(value, "chain_name", "header.chain_name")?.to_string()
+        })
+    }
+}
+
+// field order is the one the node hashes
+impl ToBytes for DeployHeader {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut bytes = self.account.to_bytes()?;
+        bytes.append(&mut self.timestamp.to_bytes()?);
+        bytes.append(&mut self.ttl.to_bytes()?);
+        bytes.append(&mut self.gas_price.to_bytes()?);
+        bytes.append(&mut self.body_hash.to_bytes()?);
+        bytes.append(&mut self.dependencies.to_bytes()?);
+        bytes.append(&mut self.chain_name.to_bytes()?);
+        Ok(bytes)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.to_bytes().map(|bytes| bytes.len()).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Approval {
+    pub signer: PublicKey,
+    pub signature: Signature
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deploy {
+    pub hash: Digest,
+    pub header: DeployHeader,
+    pub payment: ExecutableDeployItem,
+    pub session: ExecutableDeployItem,
+    pub approvals: Vec<Approval>
+}
+
+impl Deploy {
+    /// An unsigned deploy paid for with standard payment.
+    pub fn new(params: &DeployParams, session: ExecutableDeployItem) -> Result<Deploy, Error> {
+        let payment = ExecutableDeployItem::standard_payment(params.payment_amount);
+
+        let header = DeployHeader {
+            account: params.account.clone(),
+            timestamp: params.timestamp,
+            ttl: params.ttl,
+            gas_price: params.gas_price,
+            body_hash: body_hash(&payment, &session)?,
+            dependencies: Vec::new(),
+            chain_name: params.chain_name.clone()
+        };
+
+        Ok(Deploy {
+            hash: blake2b(header.to_bytes()?),
+            header,
+            payment,
+            session,
+            approvals: Vec::new()
+        })
+    }
+
+    /// Adds an approval, the key doesn't have to be the deploy's account (e.g. multisig).
+    pub fn sign(&mut self, secret_key: &SecretKey) {
+        let signer = PublicKey::from(secret_key);
+        let signature = crypto::sign(self.hash, secret_key, &signer);
+        self.approvals.push(Approval { signer, signature });
+    }
+
+    /// Checks the hashes & every approval the same way the node does when accepting a deploy.
+    pub fn verify(&self) -> Result<(), Error> {
+        if body_hash(&self.payment, &self.session)? != self.header.body_hash {
+            return Err(Error::InvalidBodyHash);
+        }
+        if blake2b(self.header.to_bytes()?) != self.hash {
+            return Err(Error::InvalidDeployHash);
+        }
+        for approval in &self.approvals {
+            crypto::verify(self.hash, &approval.signature, &approval.signer)
+                .map_err(|_| Error::InvalidSignature)?;
+        }
+        Ok(())
+    }
+
+    /// The JSON the node's `account_put_deploy` takes & `casper-client` reads from deploy files.
+    pub fn to_json(&self) -> Result<Value, Error> {
+        let approvals: Result<Vec<Value>, Error> = self.approvals.iter().map(|approval| Ok(json!({
+            "signer": serde_json::to_value(&approval.signer)?,
+            "signature": serde_json::to_value(&approval.signature)?
+        }))).collect();
+
+        Ok(json!({
+            "hash": hex::encode(self.hash),
+            "header": self.header.to_json()?,
+            "payment": self.payment.to_json()?,
+            "session": self.session.to_json()?,
+            "approvals": approvals?
+        }))
+    }
+
+    /// Reads a deploy back from its JSON, e.g. an unsigned deploy file to sign offline. Nothing
+    /// is checked here, call `verify` before trusting it.
+    pub fn from_json(value: &Value) -> Result<Deploy, Error> {
+        let approvals: Result<Vec<Approval>, Error> = value["approvals"]
+            .as_array()
+            .ok_or(Error::MissingField("approvals"))?
+            .iter()
+            .map(|approval| Ok(Approval {
+                signer: serde_json::from_value(approval["signer"].clone())?,
+                signature: serde_json::from_value(approval["signature"].clone())?
+            }))
+            .collect();
+
+        Ok(Deploy {
+            hash: json_digest(value, "hash", "hash")?,
+            header: DeployHeader::from_json(&value["header"])?,
+            payment: ExecutableDeployItem::from_json(&value["payment"])?,
+            session: ExecutableDeployItem::from_json(&value["session"])?,
+            approvals: approvals?
+        })
+    }
+}
+
+fn body_hash(payment: &ExecutableDeployItem, session: &ExecutableDeployItem) -> Result<Digest, Error> {
+    let mut bytes = payment.to_bytes()?;
+    bytes.append(&mut session.to_bytes()?);
+    Ok(blake2b(bytes))
+}
+
+fn json_str<'a>(value: &'a Value, name: &str, field: &'static str) -> Result<&'a str, Error> {
+    value[name].as_str().ok_or(Error::MissingField(field))
+}
+
+fn json_digest(value: &Value, name: &str, field: &'static str) -> Result<Digest, Error> {
+    let mut digest = [0; 32];
+    hex::decode_to_slice(json_str(value, name, field)?, &mut digest)?;
+    Ok(digest)
+}
+
+fn json_args(item: &Value) -> Result<RuntimeArgs, Error> {
+    Ok(serde_json::from_value(item["args"].clone())?)
+}
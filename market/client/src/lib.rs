@@ -0,0 +1,64 @@
+//! Rust client for the market contract. Builds typed deploys for each market entry point & for
+//! the `payment` session wasm, signs them & serializes them to the node's JSON deploy format so
+//! they can be sent with `casper-client send-deploy` or any JSON-RPC client. No node is needed.
+
+use std::fmt;
+
+use casper_types::bytesrepr;
+
+pub mod deploy;
+pub mod market;
+
+pub use deploy::{Approval, Deploy, DeployHeader, DeployParams, ExecutableDeployItem};
+pub use market::{install_market, upgrade_market, MarketClient};
+
+#[derive(Debug)]
+pub enum Error {
+    Bytes(bytesrepr::Error),
+    Json(serde_json::Error),
+    Hex(hex::FromHexError),
+    MissingField(&'static str),
+    InvalidTime(String),
+    UnsupportedDeployItem(String),
+    MissingPaymentWasm,
+    InvalidBodyHash,
+    InvalidDeployHash,
+    InvalidSignature
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Bytes(error) => write!(f, "failed to serialize deploy: {}", error),
+            Error::Json(error) => write!(f, "invalid json: {}", error),
+            Error::Hex(error) => write!(f, "invalid hex: {}", error),
+            Error::MissingField(field) => write!(f, "missing field: {}", field),
+            Error::InvalidTime(time) => write!(f, "invalid timestamp or ttl: {}", time),
+            Error::UnsupportedDeployItem(item) => write!(f, "unsupported deploy item: {}", item),
+            Error::MissingPaymentWasm => write!(f, "no payment wasm was given to the client"),
+            Error::InvalidBodyHash => write!(f, "body hash doesn't match the payment & session"),
+            Error::InvalidDeployHash => write!(f, "deploy hash doesn't match the header"),
+            Error::InvalidSignature => write!(f, "approval signature doesn't match the deploy hash"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<bytesrepr::Error> for Error {
+    fn from(error: bytesrepr::Error) -> Self {
+        Error::Bytes(error)
+    }
+}
+
+impl From<hex::FromHexError> for Error {
+    fn from(error: hex::FromHexError) -> Self {
+        Error::Hex(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
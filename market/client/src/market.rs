@@ -0,0 +1,209 @@
+use casper_types::{
+    bytesrepr::Bytes,
+    runtime_args, ContractHash, Key, RuntimeArgs, URef, U512};
+
+use crate::{
+    deploy::{Deploy, DeployParams, ExecutableDeployItem},
+    Error
+};
+
+// market entry point args, see `market/contract/src/entry_points.rs`
+pub const NFT_CONTRACT_HASH_ARG: &str = "token_contract_hash";
+pub const TOKEN_ID_ARG: &str = "token_id";
+pub const PRICE_ARG: &str = "price";
+pub const ACCEPTED_OFFER_ARG: &str = "accepted_offer";
+pub const CLAIM_PURSE_ARG: &str = "purse";
+
+// payment session args, see `payment/contract/src/main.rs`
+pub const MARKET_CONTRACT_HASH_ARG: &str = "market_contract_hash";
+pub const MARKET_ENTRY_POINT_NAME_ARG: &str = "entry_point_name";
+pub const AMOUNT_ARG: &str = "amount";
+
+// market install args, see `call` in `market/contract/src/main.rs`
+pub const MAX_OFFERS_PER_TOKEN_ARG: &str = "max_offers_per_token";
+pub const MIN_OFFER_AMOUNT_ARG: &str = "min_offer_amount";
+
+/// Installs the market wasm as a new contract package under the sending account.
+pub fn install_market(
+    params: &DeployParams,
+    market_wasm: Vec<u8>,
+    max_offers_per_token: u32,
+    min_offer_amount: U512
+) -> Result<Deploy, Error> {
+    Deploy::new(params, ExecutableDeployItem::ModuleBytes {
+        module_bytes: Bytes::from(market_wasm),
+        args: runtime_args! {
+            MAX_OFFERS_PER_TOKEN_ARG => max_offers_per_token,
+            MIN_OFFER_AMOUNT_ARG => min_offer_amount
+        }
+    })
+}
+
+/// Adds a new version to the package the sending account installed, the market wasm takes this
+/// path on its own when the account already holds the package's access key. The previous
+/// version's storage & config carry over, so there are no args.
+pub fn upgrade_market(params: &DeployParams, market_wasm: Vec<u8>) -> Result<Deploy, Error> {
+    Deploy::new(params, ExecutableDeployItem::ModuleBytes {
+        module_bytes: Bytes::from(market_wasm),
+        args: RuntimeArgs::new()
+    })
+}
+
+/// Builds unsigned deploys against an installed market. Entry points that take the caller's
+/// funds (`buy_listing`, `make_offer` & `buy_floor`) run through the `payment` session wasm,
+/// which moves `amount` into a fresh purse & hands it to the market.
+pub struct MarketClient {
+    market_contract_hash: ContractHash,
+    payment_wasm: Option<Bytes>
+}
+
+impl MarketClient {
+    pub fn new(market_contract_hash: ContractHash) -> Self {
+        MarketClient { market_contract_hash, payment_wasm: None }
+    }
+
+    pub fn with_payment_wasm(mut self, payment_wasm: Vec<u8>) -> Self {
+        self.payment_wasm = Some(Bytes::from(payment_wasm));
+        self
+    }
+
+    pub fn market_contract_hash(&self) -> ContractHash {
+        self.market_contract_hash
+    }
+
+    /// Calls any market entry point directly.
+    pub fn call(&self, params: &DeployParams, entry_point: &str, args: RuntimeArgs) -> Result<Deploy, Error> {
+        Deploy::new(params, ExecutableDeployItem::StoredContractByHash {
+            hash: self.market_contract_hash,
+            entry_point: entry_point.to_string(),
+            args
+        })
+    }
+
+    pub fn create_listing(
+        &self,
+        params: &DeployParams,
+        token_contract: &ContractHash,
+        token_id: &str,
+        price: U512
+    ) -> Result<Deploy, Error> {
+        self.call(params, "create_listing", runtime_args! {
+            NFT_CONTRACT_HASH_ARG => token_contract.to_formatted_string(),
+            TOKEN_ID_ARG => token_id.to_string(),
+            PRICE_ARG => price
+        })
+    }
+
+    /// Lists with the token moved into the market's custody until the listing closes.
+    pub fn create_custody_listing(
+        &self,
+        params: &DeployParams,
+        token_contract: &ContractHash,
+        token_id: &str,
+        price: U512
+    ) -> Result<Deploy, Error> {
+        self.call(params, "create_custody_listing", runtime_args! {
+            NFT_CONTRACT_HASH_ARG => token_contract.to_formatted_string(),
+            TOKEN_ID_ARG => token_id.to_string(),
+            PRICE_ARG => price
+        })
+    }
+
+    pub fn cancel_listing(
+        &self,
+        params: &DeployParams,
+        token_contract: &ContractHash,
+        token_id: &str
+    ) -> Result<Deploy, Error> {
+        self.call(params, "cancel_listing", runtime_args! {
+            NFT_CONTRACT_HASH_ARG => token_contract.to_formatted_string(),
+            TOKEN_ID_ARG => token_id.to_string()
+        })
+    }
+
+    pub fn withdraw_offer(
+        &self,
+        params: &DeployParams,
+        token_contract: &ContractHash,
+        token_id: &str
+    ) -> Result<Deploy, Error> {
+        self.call(params, "withdraw_offer", runtime_args! {
+            NFT_CONTRACT_HASH_ARG => token_contract.to_formatted_string(),
+            TOKEN_ID_ARG => token_id.to_string()
+        })
+    }
+
+    pub fn accept_offer(
+        &self,
+        params: &DeployParams,
+        token_contract: &ContractHash,
+        token_id: &str,
+        accepted_offer: &Key
+    ) -> Result<Deploy, Error> {
+        self.call(params, "accept_offer", runtime_args! {
+            NFT_CONTRACT_HASH_ARG => token_contract.to_formatted_string(),
+            TOKEN_ID_ARG => token_id.to_string(),
+            ACCEPTED_OFFER_ARG => accepted_offer.to_formatted_string()
+        })
+    }
+
+    /// Claims to the account's main purse.
+    pub fn claim(&self, params: &DeployParams) -> Result<Deploy, Error> {
+        self.call(params, "claim", runtime_args! {
+            CLAIM_PURSE_ARG => Option::<URef>::None
+        })
+    }
+
+    pub fn buy_listing(
+        &self,
+        params: &DeployParams,
+        token_contract: &ContractHash,
+        token_id: &str,
+        amount: U512
+    ) -> Result<Deploy, Error> {
+        self.payment_session(params, "buy_listing", token_contract, token_id, amount)
+    }
+
+    pub fn make_offer(
+        &self,
+        params: &DeployParams,
+        token_contract: &ContractHash,
+        token_id: &str,
+        amount: U512
+    ) -> Result<Deploy, Error> {
+        self.payment_session(params, "make_offer", token_contract, token_id, amount)
+    }
+
+    /// The payment wasm passes `amount` on as `max_price`, `buy_floor` takes no token id.
+    pub fn buy_floor(
+        &self,
+        params: &DeployParams,
+        token_contract: &ContractHash,
+        max_price: U512
+    ) -> Result<Deploy, Error> {
+        self.payment_session(params, "buy_floor", token_contract, "", max_price)
+    }
+
+    /// Runs the `payment` wasm as session code, funding `entry_point_name` with `amount` motes.
+    pub fn payment_session(
+        &self,
+        params: &DeployParams,
+        entry_point_name: &str,
+        token_contract: &ContractHash,
+        token_id: &str,
+        amount: U512
+    ) -> Result<Deploy, Error> {
+        let module_bytes = self.payment_wasm.clone().ok_or(Error::MissingPaymentWasm)?;
+
+        Deploy::new(params, ExecutableDeployItem::ModuleBytes {
+            module_bytes,
+            args: runtime_args! {
+                MARKET_CONTRACT_HASH_ARG => self.market_contract_hash.to_formatted_string(),
+                MARKET_ENTRY_POINT_NAME_ARG => entry_point_name.to_string(),
+                NFT_CONTRACT_HASH_ARG => token_contract.to_formatted_string(),
+                TOKEN_ID_ARG => token_id.to_string(),
+                AMOUNT_ARG => amount
+            }
+        })
+    }
+}
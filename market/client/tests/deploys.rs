@@ -0,0 +1,195 @@
+use casper_types::{
+    account::AccountHash,
+    ContractHash, Key, PublicKey, SecretKey, U512};
+use market_client::{
+    install_market, upgrade_market,
+    Deploy, DeployParams, Error, ExecutableDeployItem, MarketClient};
+use serde_json::Value;
+
+const PAYMENT_WASM: &[u8] = b"\0asm\x01\0\0\0";
+
+fn secret_key(byte: u8) -> SecretKey {
+    SecretKey::ed25519_from_bytes([byte; 32]).unwrap()
+}
+
+fn params() -> DeployParams {
+    let mut params = DeployParams::new("casper-net-1", PublicKey::from(&secret_key(1)), U512::from(3_000_000_000u64));
+    // 2022-01-01T00:00:00.000Z
+    params.timestamp = 1_640_995_200_000;
+    params
+}
+
+fn client() -> MarketClient {
+    MarketClient::new(ContractHash::new([0xcc; 32])).with_payment_wasm(PAYMENT_WASM.to_vec())
+}
+
+fn token_contract() -> ContractHash {
+    ContractHash::new([0xaa; 32])
+}
+
+// `args` is a list of [name, CLValue] pairs in the node's JSON
+fn json_arg<'a>(item: &'a Value, name: &str) -> &'a Value {
+    item["args"].as_array()
+        .unwrap()
+        .iter()
+        .find(|arg| arg[0] == name)
+        .map(|arg| &arg[1])
+        .unwrap_or_else(|| panic!("missing arg {}", name))
+}
+
+#[test]
+fn should_build_the_same_deploy_for_the_same_params() {
+    let a = client().create_listing(&params(), &token_contract(), "1", U512::from(100)).unwrap();
+    let b = client().create_listing(&params(), &token_contract(), "1", U512::from(100)).unwrap();
+    assert_eq!(a.hash, b.hash);
+
+    let c = client().create_listing(&params(), &token_contract(), "1", U512::from(101)).unwrap();
+    assert_ne!(a.hash, c.hash);
+    assert_ne!(a.header.body_hash, c.header.body_hash);
+}
+
+#[test]
+fn should_sign_and_verify_a_deploy() {
+    let mut deploy = client().cancel_listing(&params(), &token_contract(), "1").unwrap();
+    deploy.sign(&secret_key(1));
+    deploy.sign(&secret_key(2));
+
+    assert_eq!(deploy.approvals.len(), 2);
+    assert_eq!(deploy.approvals[0].signer, params().account);
+    deploy.verify().unwrap();
+}
+
+#[test]
+fn should_reject_a_tampered_deploy() {
+    let mut deploy = client().withdraw_offer(&params(), &token_contract(), "1").unwrap();
+    deploy.sign(&secret_key(1));
+
+    let mut tampered = deploy.clone();
+    tampered.session = ExecutableDeployItem::standard_payment(U512::one());
+    assert!(matches!(tampered.verify(), Err(Error::InvalidBodyHash)));
+
+    let mut tampered = deploy.clone();
+    tampered.header.gas_price = 2;
+    assert!(matches!(tampered.verify(), Err(Error::InvalidDeployHash)));
+
+    let mut tampered = deploy;
+    tampered.approvals[0].signer = PublicKey::from(&secret_key(2));
+    assert!(matches!(tampered.verify(), Err(Error::InvalidSignature)));
+}
+
+#[test]
+fn should_serialize_a_direct_call_to_node_json() {
+    let buyer = Key::Account(AccountHash::new([0x44; 32]));
+    let mut deploy = client().accept_offer(&params(), &token_contract(), "1", &buyer).unwrap();
+    deploy.sign(&secret_key(1));
+    let json = deploy.to_json().unwrap();
+
+    assert_eq!(json["hash"], hex::encode(deploy.hash));
+    assert_eq!(json["header"]["timestamp"], "2022-01-01T00:00:00.000Z");
+    assert_eq!(json["header"]["ttl"], "30m");
+    assert_eq!(json["header"]["gas_price"], 1);
+    assert_eq!(json["header"]["chain_name"], "casper-net-1");
+    assert_eq!(json["header"]["account"], params().account.to_hex());
+    assert_eq!(json["approvals"][0]["signer"], params().account.to_hex());
+
+    let payment = &json["payment"]["ModuleBytes"];
+    assert_eq!(payment["module_bytes"], "");
+    assert_eq!(json_arg(payment, "amount")["cl_type"], "U512");
+
+    let session = &json["session"]["StoredContractByHash"];
+    assert_eq!(session["hash"], hex::encode([0xcc; 32]));
+    assert_eq!(session["entry_point"], "accept_offer");
+    assert_eq!(json_arg(session, "token_contract_hash")["parsed"], token_contract().to_formatted_string());
+    assert_eq!(json_arg(session, "token_id")["cl_type"], "String");
+    assert_eq!(json_arg(session, "accepted_offer")["parsed"], buyer.to_formatted_string());
+}
+
+#[test]
+fn should_pass_claim_an_empty_purse() {
+    let json = client().claim(&params()).unwrap().to_json().unwrap();
+    let session = &json["session"]["StoredContractByHash"];
+
+    assert_eq!(session["entry_point"], "claim");
+    assert_eq!(json_arg(session, "purse")["cl_type"], serde_json::json!({ "Option": "URef" }));
+}
+
+#[test]
+fn should_call_the_custody_entry_point_with_the_listing_args() {
+    let json = client().create_custody_listing(&params(), &token_contract(), "1", U512::from(100)).unwrap()
+        .to_json()
+        .unwrap();
+    let session = &json["session"]["StoredContractByHash"];
+
+    assert_eq!(session["entry_point"], "create_custody_listing");
+    assert_eq!(json_arg(session, "token_id")["parsed"], "1");
+    assert_eq!(json_arg(session, "price")["parsed"], "100");
+}
+
+#[test]
+fn should_fund_payment_entry_points_through_the_session_wasm() {
+    let amount = U512::from(90_000_000_000u64);
+    let json = client().make_offer(&params(), &token_contract(), "2", amount).unwrap().to_json().unwrap();
+    let session = &json["session"]["ModuleBytes"];
+
+    assert_eq!(session["module_bytes"], hex::encode(PAYMENT_WASM));
+    assert_eq!(json_arg(session, "market_contract_hash")["parsed"], ContractHash::new([0xcc; 32]).to_formatted_string());
+    assert_eq!(json_arg(session, "entry_point_name")["parsed"], "make_offer");
+    assert_eq!(json_arg(session, "token_id")["parsed"], "2");
+    assert_eq!(json_arg(session, "amount")["cl_type"], "U512");
+    assert_eq!(json_arg(session, "amount")["parsed"], amount.to_string());
+}
+
+#[test]
+fn should_require_the_payment_wasm_for_session_entry_points() {
+    let client = MarketClient::new(ContractHash::new([0xcc; 32]));
+
+    assert!(matches!(
+        client.buy_listing(&params(), &token_contract(), "1", U512::one()),
+        Err(Error::MissingPaymentWasm)
+    ));
+    assert!(client.create_listing(&params(), &token_contract(), "1", U512::one()).is_ok());
+}
+
+#[test]
+fn should_build_a_deploy_for_any_session() {
+    let session = ExecutableDeployItem::ModuleBytes {
+        module_bytes: PAYMENT_WASM.to_vec().into(),
+        args: Default::default()
+    };
+    let deploy = Deploy::new(&params(), session.clone()).unwrap();
+
+    assert_eq!(deploy.session, session);
+    assert!(deploy.approvals.is_empty());
+    deploy.verify().unwrap();
+}
+
+#[test]
+fn should_read_back_a_deploy_from_its_json() {
+    let mut deploy = client().buy_floor(&params(), &token_contract(), U512::from(50)).unwrap();
+    deploy.sign(&secret_key(1));
+
+    let read = Deploy::from_json(&deploy.to_json().unwrap()).unwrap();
+    assert_eq!(read, deploy);
+    read.verify().unwrap();
+}
+
+#[test]
+fn should_sign_a_deploy_read_from_an_unsigned_file() {
+    let unsigned = client().claim(&params()).unwrap().to_json().unwrap();
+
+    let mut deploy = Deploy::from_json(&unsigned).unwrap();
+    deploy.sign(&secret_key(1));
+    deploy.verify().unwrap();
+    assert_eq!(deploy.hash, Deploy::from_json(&unsigned).unwrap().hash);
+}
+
+#[test]
+fn should_pass_install_args_only_on_install() {
+    let install = install_market(&params(), PAYMENT_WASM.to_vec(), 10, U512::from(1_000)).unwrap().to_json().unwrap();
+    let session = &install["session"]["ModuleBytes"];
+    assert_eq!(json_arg(session, "max_offers_per_token")["cl_type"], "U32");
+    assert_eq!(json_arg(session, "min_offer_amount")["cl_type"], "U512");
+
+    let upgrade = upgrade_market(&params(), PAYMENT_WASM.to_vec()).unwrap().to_json().unwrap();
+    assert!(upgrade["session"]["ModuleBytes"]["args"].as_array().unwrap().is_empty());
+}
@@ -0,0 +1,190 @@
+use core::marker::PhantomData;
+
+use alloc::{
+    string::String,
+    vec, vec::Vec,
+    collections::BTreeMap
+};
+
+use casper_contract::contract_api::runtime;
+use casper_types::{
+    bytesrepr::FromBytes,
+    contracts::{EntryPoint, EntryPointAccess, EntryPointType, EntryPoints},
+    CLType, CLTyped, Key, Parameter, URef, U512};
+
+use market_core::{CollectionStats, Listing, Sale};
+
+/// A named runtime argument tied to the type the contract reads it as. Both the entry point
+/// schema & the runtime read go through this, so the declared type can't drift from the code.
+pub struct Arg<T> {
+    pub name: &'static str,
+    _type: PhantomData<T>
+}
+
+impl<T> Arg<T> {
+    pub const fn new(name: &'static str) -> Self {
+        Arg { name, _type: PhantomData }
+    }
+}
+
+impl<T: CLTyped + FromBytes> Arg<T> {
+    pub fn get(&self) -> T {
+        runtime::get_named_arg(self.name)
+    }
+
+    pub fn parameter(&self) -> Parameter {
+        Parameter::new(self.name, T::cl_type())
+    }
+}
+
+pub const NFT_CONTRACT_HASH_ARG: Arg<String> = Arg::new("token_contract_hash");
+pub const TOKEN_ID_ARG: Arg<String> = Arg::new("token_id");
+pub const PRICE_ARG: Arg<U512> = Arg::new("price");
+pub const BUYER_PURSE_ARG: Arg<URef> = Arg::new("purse");
+pub const ACCEPTED_OFFER_ARG: Arg<String> = Arg::new("accepted_offer");
+pub const MAX_PRICE_ARG: Arg<U512> = Arg::new("max_price");
+// accounts may pass None & get paid to their main purse, contracts must supply one
+pub const CLAIM_PURSE_ARG: Arg<Option<URef>> = Arg::new("purse");
+
+// install time config
+pub const MAX_OFFERS_PER_TOKEN_ARG: Arg<u32> = Arg::new("max_offers_per_token");
+pub const MIN_OFFER_AMOUNT_ARG: Arg<U512> = Arg::new("min_offer_amount");
+
+fn public_entry_point(name: &str, params: Vec<Parameter>, ret: CLType) -> EntryPoint {
+    EntryPoint::new(
+        name,
+        params,
+        ret,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    )
+}
+
+pub fn get_entry_points() -> EntryPoints {
+    let mut entry_points = EntryPoints::new();
+
+    entry_points.add_entry_point(public_entry_point(
+        "create_listing",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter(),
+            TOKEN_ID_ARG.parameter(),
+            PRICE_ARG.parameter()
+        ],
+        <()>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "create_custody_listing",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter(),
+            TOKEN_ID_ARG.parameter(),
+            PRICE_ARG.parameter()
+        ],
+        <()>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "buy_listing",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter(),
+            TOKEN_ID_ARG.parameter(),
+            BUYER_PURSE_ARG.parameter()
+        ],
+        <()>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "buy_floor",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter(),
+            MAX_PRICE_ARG.parameter(),
+            BUYER_PURSE_ARG.parameter()
+        ],
+        <()>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "cancel_listing",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter(),
+            TOKEN_ID_ARG.parameter()
+        ],
+        <()>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "make_offer",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter(),
+            TOKEN_ID_ARG.parameter(),
+            BUYER_PURSE_ARG.parameter()
+        ],
+        <()>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "withdraw_offer",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter(),
+            TOKEN_ID_ARG.parameter()
+        ],
+        <()>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "accept_offer",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter(),
+            TOKEN_ID_ARG.parameter(),
+            ACCEPTED_OFFER_ARG.parameter()
+        ],
+        <()>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "get_listing",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter(),
+            TOKEN_ID_ARG.parameter()
+        ],
+        Option::<Listing>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "get_listing_custody",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter(),
+            TOKEN_ID_ARG.parameter()
+        ],
+        bool::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "get_offers",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter(),
+            TOKEN_ID_ARG.parameter()
+        ],
+        BTreeMap::<Key, U512>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "get_collection_stats",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter()
+        ],
+        CollectionStats::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "get_floor_price",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter()
+        ],
+        Option::<U512>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "get_sales_history",
+        vec![
+            NFT_CONTRACT_HASH_ARG.parameter(),
+            TOKEN_ID_ARG.parameter()
+        ],
+        Vec::<Sale>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "claim",
+        vec![
+            CLAIM_PURSE_ARG.parameter()
+        ],
+        <()>::cl_type()
+    ));
+    entry_points
+}
@@ -0,0 +1,215 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec
+};
+
+use casper_contract::{
+    contract_api::{runtime, storage, system},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+
+use casper_types::{
+    account::AccountHash,
+    system::CallStackElement,
+    contracts::NamedKeys,
+    bytesrepr::{Bytes, FromBytes, ToBytes},
+    runtime_args, RuntimeArgs,
+    ApiError, Key, URef, ContractHash, ContractPackageHash, CLTyped, U256, U512};
+
+use market_core::{
+    Error, MarketEvent, MarketHost, Purse, schemas,
+    EVENTS_DICT, EVENTS_LENGTH, EVENTS_SCHEMA, CES_VERSION_KEY, CES_VERSION};
+
+pub const OFFERS_PURSE: &str = "offers_purse";
+pub const CLAIMS_PURSE: &str = "claims_purse";
+
+/// `MarketHost` over the casper runtime, storage lives under the contract's named keys.
+pub struct ContractHost;
+
+impl MarketHost for ContractHost {
+    fn dictionary_get<T: CLTyped + FromBytes>(&mut self, dictionary: &str, key: &str) -> Option<T> {
+        storage::dictionary_get(get_dictionary_uref(dictionary), key)
+            .ok()
+            .flatten()
+    }
+
+    fn dictionary_put<T: CLTyped + ToBytes>(&mut self, dictionary: &str, key: &str, value: T) -> () {
+        storage::dictionary_put(get_dictionary_uref(dictionary), key, value);
+    }
+
+    fn named_value<T: CLTyped + FromBytes>(&mut self, name: &str) -> Option<T> {
+        let uref = runtime::get_key(name)?.into_uref()?;
+        storage::read(uref).ok().flatten()
+    }
+
+    // markets installed before the key existed get it on their first write
+    fn put_named_value<T: CLTyped + ToBytes>(&mut self, name: &str, value: T) -> () {
+        match runtime::get_key(name) {
+            Some(key) => storage::write(key.into_uref().unwrap_or_revert_with(ApiError::UnexpectedKeyVariant), value),
+            None => runtime::put_key(name, storage::new_uref(value).into())
+        }
+    }
+
+    fn blake2b(&self, bytes: &[u8]) -> [u8; 32] {
+        runtime::blake2b(bytes)
+    }
+
+    fn block_time(&self) -> u64 {
+        u64::from(runtime::get_blocktime())
+    }
+
+    fn emit(&mut self, event: MarketEvent) -> () {
+        emit(event)
+    }
+
+    fn market_package(&self) -> ContractPackageHash {
+        contract_package_hash()
+    }
+
+    fn owner_of(&mut self, token_contract: ContractHash, token_id: U256) -> Option<Key> {
+        runtime::call_contract::<Option<Key>>(
+            token_contract,
+            "owner_of",
+            runtime_args! {
+                "token_id" => token_id
+              }
+        )
+    }
+
+    fn get_approved(&mut self, token_contract: ContractHash, owner: Key, token_id: U256) -> Option<Key> {
+        runtime::call_contract::<Option<Key>>(
+            token_contract,
+            "get_approved",
+            runtime_args! {
+                "owner" => owner,
+                "token_id" => token_id
+              }
+        )
+    }
+
+    // a token contract refusing the transfer reverts the whole call with its own error
+    fn transfer_from(
+        &mut self,
+        token_contract: ContractHash,
+        sender: Key,
+        recipient: Key,
+        token_ids: Vec<U256>
+    ) -> Result<(), ApiError> {
+        runtime::call_contract::<()>(
+            token_contract,
+            "transfer_from",
+            runtime_args! {
+                "sender" => sender,
+                "recipient" => recipient,
+                "token_ids" => token_ids,
+              }
+        );
+        Ok(())
+    }
+
+    fn balance(&mut self, purse: Purse) -> Option<U512> {
+        system::get_purse_balance(purse_uref(purse))
+    }
+
+    fn transfer_to_purse(&mut self, source: Purse, target: Purse, amount: U512) -> Result<(), ApiError> {
+        system::transfer_from_purse_to_purse(purse_uref(source), purse_uref(target), amount, None)
+    }
+
+    fn transfer_to_account(&mut self, source: Purse, target: AccountHash, amount: U512) -> Result<(), ApiError> {
+        system::transfer_from_purse_to_account(purse_uref(source), target, amount, None)
+            .map(|_| ())
+    }
+}
+
+// the market's own purses are created the first time they're needed
+fn purse_uref(purse: Purse) -> URef {
+    match purse {
+        Purse::External(uref) => uref,
+        Purse::Offers => get_purse(OFFERS_PURSE),
+        Purse::Claims => get_purse(CLAIMS_PURSE)
+    }
+}
+
+pub fn contract_package_hash() -> ContractPackageHash {
+    let call_stacks = runtime::get_call_stack();
+    let last_entry = call_stacks.last().unwrap_or_revert();
+    let package_hash: Option<ContractPackageHash> = match last_entry {
+        CallStackElement::StoredContract {
+            contract_package_hash,
+            contract_hash: _,
+        } => Some(*contract_package_hash),
+        _ => None,
+    };
+    package_hash.unwrap_or_revert()
+}
+
+// the element below us on the call stack, so contracts calling through get their own identity
+pub fn get_immediate_caller() -> Key {
+    let call_stack = runtime::get_call_stack();
+    let caller = call_stack.iter().rev().nth(1).unwrap_or_revert();
+    match caller {
+        CallStackElement::Session { account_hash } => Key::Account(*account_hash),
+        CallStackElement::StoredSession { account_hash, .. } => Key::Account(*account_hash),
+        CallStackElement::StoredContract { contract_package_hash, .. } => Key::from(*contract_package_hash),
+    }
+}
+
+pub fn get_dictionary_uref(key: &str) -> URef {
+    match runtime::get_key(key) {
+        Some(uref_key) => uref_key.into_uref().unwrap_or_revert(),
+        None => storage::new_dictionary(key).unwrap_or_revert(),
+    }
+}
+
+pub fn read_named_uref(name: &str) -> URef {
+    runtime::get_key(name)
+        .unwrap_or_revert_with(ApiError::MissingKey)
+        .into_uref()
+        .unwrap_or_revert_with(ApiError::UnexpectedKeyVariant)
+}
+
+pub fn get_purse(purse_name: &str) -> URef {
+    let purse = if !runtime::has_key(&purse_name) {
+        let purse = system::create_purse();
+        runtime::put_key(&purse_name, purse.into());
+        purse
+    } else {
+        let destination_purse_key = runtime::get_key(&purse_name).unwrap_or_revert_with(
+            Error::OfferPurseRetrieval
+        );
+        match destination_purse_key.as_uref() {
+            Some(uref) => *uref,
+            None => runtime::revert(Error::OfferPurseRetrieval),
+        }
+    };
+    return purse;
+}
+
+pub fn emit(event: MarketEvent) {
+    // append only, events are keyed by their index so consumers can replay them in order
+    let length_uref: URef = read_named_uref(EVENTS_LENGTH);
+    let events_length: u32 = storage::read(length_uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert_with(ApiError::ValueNotFound);
+    let events_uref: URef = read_named_uref(EVENTS_DICT);
+
+    // stored as `List<U8>` rather than `Any` so node tooling & SDKs can parse the value
+    let event_bytes = Bytes::from(event.to_bytes().unwrap_or_revert());
+    storage::dictionary_put(events_uref, &events_length.to_string(), event_bytes);
+    storage::write(
+        length_uref,
+        events_length.checked_add(1).unwrap_or_revert_with(Error::ArithmeticOverflow)
+    );
+}
+
+// the CES keys have to be on the contract from install so tooling can find the schema
+pub fn init_events(named_keys: &mut NamedKeys) -> () {
+    // the dictionary lands in the installing account's keys, it only belongs to the contract
+    let events_uref: URef = storage::new_dictionary(EVENTS_DICT).unwrap_or_revert();
+    runtime::remove_key(EVENTS_DICT);
+
+    named_keys.insert(String::from(EVENTS_DICT), events_uref.into());
+    named_keys.insert(String::from(EVENTS_LENGTH), storage::new_uref(0u32).into());
+    named_keys.insert(String::from(EVENTS_SCHEMA), storage::new_uref(schemas()).into());
+    named_keys.insert(String::from(CES_VERSION_KEY), storage::new_uref(String::from(CES_VERSION)).into());
+}
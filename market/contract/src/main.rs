@@ -8,349 +8,223 @@ compile_error!("target arch should be wasm32: compile with '--target wasm32-unkn
 // `no_std` environment.
 extern crate alloc;
 
-use alloc::{
-    string::String,
-    str,
-    vec, vec::Vec,
-    collections::BTreeMap
-};
+use alloc::string::String;
 
 use casper_contract::{
-    contract_api::{runtime, storage, system},
+    contract_api::{runtime, storage},
     unwrap_or_revert::UnwrapOrRevert,
 };
 use casper_types::{
-    contracts::{EntryPoint, EntryPointAccess, EntryPointType, EntryPoints},
-    runtime_args, RuntimeArgs, Parameter,
-    Key, URef, ContractHash, CLTyped, U256, U512};
-
-use event::{MarketEvent};
-mod event;
-use data::{
-            Error, Listing, contract_package_hash, transfer_approved, get_id,
-            get_token_owner, token_id_to_vec, get_listing,
-            get_listing_dictionary, get_offers, get_purse, emit, force_cancel_listing};
-mod data;
-
-const OFFERS_PURSE: &str = "offers_purse";
-
-const NFT_CONTRACT_HASH_ARG: &str = "token_contract_hash";
-const TOKEN_ID_ARG: &str = "token_id";
-const PRICE_ARG: &str = "price";
-const BUYER_PURSE_ARG: &str = "purse";
-const ACCEPTED_OFFER_ARG: &str = "accepted_offer";
+    contracts::NamedKeys,
+    ApiError, CLValue, Key, URef, ContractHash, ContractPackageHash, U512};
+
+use market_core::{Market, MAX_OFFERS_PER_TOKEN, MIN_OFFER_AMOUNT, REENTRANCY_LOCK};
+use host::{ContractHost, get_immediate_caller, init_events};
+mod host;
+use entry_points::{
+            get_entry_points, NFT_CONTRACT_HASH_ARG, TOKEN_ID_ARG, PRICE_ARG,
+            BUYER_PURSE_ARG, ACCEPTED_OFFER_ARG, CLAIM_PURSE_ARG,
+            MAX_PRICE_ARG, MAX_OFFERS_PER_TOKEN_ARG, MIN_OFFER_AMOUNT_ARG};
+mod entry_points;
+
+// installer account named keys
+const MARKET_CONTRACT_HASH_KEY: &str = "market_contract_hash";
+const MARKET_PACKAGE_HASH_KEY: &str = "market_contract_package_hash";
+const MARKET_PACKAGE_ACCESS_KEY: &str = "market_contract_package_access";
+
+// the rules live in market-core, the entry points only read args & revert on its errors
+fn market() -> Market<ContractHost> {
+    Market::new(ContractHost)
+}
 
 #[no_mangle]
 pub extern "C" fn create_listing() -> () {
-    let token_owner = Key::Account(runtime::get_caller());
-    let token_contract_string: String = runtime::get_named_arg(NFT_CONTRACT_HASH_ARG);
-    let token_contract_hash: ContractHash = ContractHash::from_formatted_str(&token_contract_string).unwrap();
-    let token_id: String = runtime::get_named_arg(TOKEN_ID_ARG);
-    let price: U512 = runtime::get_named_arg(PRICE_ARG);
-
-    if token_owner != get_token_owner(token_contract_hash, &token_id).unwrap() {
-        runtime::revert(Error::PermissionDenied);
-    }
-    
-    if !transfer_approved(token_contract_hash, &token_id, token_owner) {
-        runtime::revert(Error::NeedsTransferApproval);
-    }
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
+    let token_id: String = TOKEN_ID_ARG.get();
+    let price: U512 = PRICE_ARG.get();
 
-    let listing = Listing {
-        token_contract: token_contract_hash,
-        token_id: token_id.clone(),
-        price: price,
-        seller: token_owner
-    };
+    market().create_listing(get_immediate_caller(), &token_contract_string, &token_id, price)
+        .unwrap_or_revert()
+}
+
+#[no_mangle]
+pub extern "C" fn create_custody_listing() -> () {
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
+    let token_id: String = TOKEN_ID_ARG.get();
+    let price: U512 = PRICE_ARG.get();
 
-    let listing_id: String = get_id(&token_contract_string, &token_id);
-    let dictionary_uref: URef = get_listing_dictionary();
-    storage::dictionary_put(dictionary_uref, &listing_id, listing);
-
-    emit(&MarketEvent::ListingCreated {
-        package: contract_package_hash(),
-        seller: token_owner,
-        token_contract: token_contract_string,
-        token_id: token_id,
-        price: price
-    })
+    market().create_custody_listing(get_immediate_caller(), &token_contract_string, &token_id, price)
+        .unwrap_or_revert()
 }
 
 #[no_mangle]
 pub fn buy_listing() -> () {
-    let buyer = Key::Account(runtime::get_caller());
-    let token_contract_string: String = runtime::get_named_arg(NFT_CONTRACT_HASH_ARG);
-    let token_contract_hash: ContractHash = ContractHash::from_formatted_str(&token_contract_string).unwrap();
-    let token_id: String = runtime::get_named_arg(TOKEN_ID_ARG);
-    let token_ids: Vec<U256> = token_id_to_vec(&token_id);
-    let listing_id: String = get_id(&token_contract_string, &token_id);
-    let (listing, dictionary_uref) = get_listing(&listing_id);
-    let buyer_purse: URef = runtime::get_named_arg(BUYER_PURSE_ARG);
-    let purse_balance: U512 = system::get_purse_balance(buyer_purse).unwrap();
-
-    if purse_balance < listing.price {
-        runtime::revert(Error::BalanceInsufficient);
-    }
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
+    let token_id: String = TOKEN_ID_ARG.get();
+    let buyer_purse: URef = BUYER_PURSE_ARG.get();
 
-    let seller = get_token_owner(token_contract_hash, &token_id).unwrap();
-
-    system::transfer_from_purse_to_account(
-        buyer_purse,
-        seller.into_account().unwrap_or_revert(),
-        listing.price,
-        None
-    ).unwrap_or_revert();
-
-    runtime::call_contract::<()>(
-        token_contract_hash,
-        "transfer_from",
-        runtime_args! {
-            "sender" => seller,
-            "recipient" => buyer,
-            "token_ids" => token_ids,
-          }
-    );
-
-    storage::dictionary_put(dictionary_uref, &listing_id, None::<Listing>);
-
-    emit(&MarketEvent::ListingPurchased {
-        package: contract_package_hash(),
-        seller: seller,
-        buyer: buyer,
-        token_contract: token_contract_string,
-        token_id: token_id,
-        price: listing.price
-    })
+    market().buy_listing(get_immediate_caller(), &token_contract_string, &token_id, buyer_purse)
+        .unwrap_or_revert()
 }
 
 #[no_mangle]
-pub fn cancel_listing() -> () {
-    let caller = Key::Account(runtime::get_caller());
-    let token_contract_string: String = runtime::get_named_arg(NFT_CONTRACT_HASH_ARG);
-    let token_contract_hash: ContractHash = ContractHash::from_formatted_str(&token_contract_string).unwrap();
-    let token_id: String = runtime::get_named_arg(TOKEN_ID_ARG);
-    let listing_id: String = get_id(&token_contract_string, &token_id);
-    let seller = get_token_owner(token_contract_hash, &token_id).unwrap();
-
-    if caller != seller {
-        runtime::revert(Error::PermissionDenied);
-    }
+pub extern "C" fn buy_floor() -> () {
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
+    let max_price: U512 = MAX_PRICE_ARG.get();
+    let buyer_purse: URef = BUYER_PURSE_ARG.get();
 
-    let (_listing, dictionary_uref) = get_listing(&listing_id);
-    storage::dictionary_put(dictionary_uref, &listing_id, None::<Listing>);
+    market().buy_floor(get_immediate_caller(), &token_contract_string, max_price, buyer_purse)
+        .unwrap_or_revert()
+}
 
-    emit(&MarketEvent::ListingCanceled {
-        package: contract_package_hash(),
-        token_contract: token_contract_string,
-        token_id: token_id
-    })
+#[no_mangle]
+pub fn cancel_listing() -> () {
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
+    let token_id: String = TOKEN_ID_ARG.get();
+
+    market().cancel_listing(get_immediate_caller(), &token_contract_string, &token_id)
+        .unwrap_or_revert()
 }
 
 #[no_mangle]
 pub extern "C" fn make_offer() -> () {
-    let bidder = Key::Account(runtime::get_caller());
-    let token_contract_string: String = runtime::get_named_arg(NFT_CONTRACT_HASH_ARG);
-    let token_id: String = runtime::get_named_arg(TOKEN_ID_ARG);
-    let offers_id: String = get_id(&token_contract_string, &token_id);
-    let bidder_purse: URef = runtime::get_named_arg(BUYER_PURSE_ARG);
-    let purse_balance: U512 = system::get_purse_balance(bidder_purse).unwrap();
-
-    let (mut offers, dictionary_uref): (BTreeMap<Key, U512>, URef) = get_offers(&offers_id);
-    
-    let offers_purse = get_purse(OFFERS_PURSE);
-
-    // TODO: rebalance current offer instead of error
-    match offers.get(&bidder) {
-        Some(_) => runtime::revert(Error::OfferExists),
-        None => ()
-    }
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
+    let token_id: String = TOKEN_ID_ARG.get();
+    let bidder_purse: URef = BUYER_PURSE_ARG.get();
 
-    offers.insert(bidder, purse_balance);
-    system::transfer_from_purse_to_purse(bidder_purse, offers_purse, purse_balance, None).unwrap_or_revert();
-    storage::dictionary_put(dictionary_uref, &offers_id, offers);
-
-    emit(&MarketEvent::OfferCreated {
-        package: contract_package_hash(),
-        buyer: bidder,
-        token_contract: token_contract_string,
-        token_id: token_id,
-        price: purse_balance
-    })
+    market().make_offer(get_immediate_caller(), &token_contract_string, &token_id, bidder_purse)
+        .unwrap_or_revert()
 }
 
 #[no_mangle]
 pub extern "C" fn withdraw_offer() -> () {
-    let bidder = Key::Account(runtime::get_caller());
-    let token_contract_string: String = runtime::get_named_arg(NFT_CONTRACT_HASH_ARG);
-    let token_id: String = runtime::get_named_arg(TOKEN_ID_ARG);
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
+    let token_id: String = TOKEN_ID_ARG.get();
 
-    let offers_id: String = get_id(&token_contract_string, &token_id);
+    market().withdraw_offer(get_immediate_caller(), &token_contract_string, &token_id)
+        .unwrap_or_revert()
+}
 
-    let (mut offers, dictionary_uref):
-        (BTreeMap<Key, U512>, URef) = get_offers(&offers_id);
+#[no_mangle]
+pub extern "C" fn accept_offer() -> () {
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
+    let token_id: String = TOKEN_ID_ARG.get();
+    let accepted_offer: String = ACCEPTED_OFFER_ARG.get();
 
-    let amount: U512 = offers.get(&bidder)
-        .unwrap_or_revert_with(Error::NoMatchingOffer)
-        .clone();
+    market().accept_offer(get_immediate_caller(), &token_contract_string, &token_id, &accepted_offer)
+        .unwrap_or_revert()
+}
 
-    let offers_purse = get_purse(OFFERS_PURSE);
+#[no_mangle]
+pub extern "C" fn get_collection_stats() -> () {
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
 
-    system::transfer_from_purse_to_account(
-        offers_purse,
-        bidder.into_account().unwrap_or_revert(),
-        amount.clone(),
-        None
-    ).unwrap_or_revert();
+    let stats = market().get_collection_stats(&token_contract_string).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(stats).unwrap_or_revert())
+}
 
-    offers.remove(&bidder);
-    storage::dictionary_put(dictionary_uref, &offers_id, offers);
+#[no_mangle]
+pub extern "C" fn get_floor_price() -> () {
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
 
-    emit(&MarketEvent::OfferWithdraw {
-        package: contract_package_hash(),
-        buyer: bidder,
-        token_contract: token_contract_string,
-        token_id: token_id
-    })
+    let floor_price = market().get_floor_price(&token_contract_string).unwrap_or_revert();
+    runtime::ret(CLValue::from_t(floor_price).unwrap_or_revert())
 }
 
 #[no_mangle]
-pub extern "C" fn accept_offer() -> () {
-    let seller = Key::Account(runtime::get_caller());
-    let token_contract_string: String = runtime::get_named_arg(NFT_CONTRACT_HASH_ARG);
-    let token_contract_hash: ContractHash = ContractHash::from_formatted_str(&token_contract_string).unwrap();
-    let token_id: String = runtime::get_named_arg(TOKEN_ID_ARG);
-    let token_ids: Vec<U256> = token_id_to_vec(&token_id);
-    let offer_account_hash: String = runtime::get_named_arg(ACCEPTED_OFFER_ARG);
-    let accepted_bidder_hash: Key = Key::from_formatted_str(&offer_account_hash).unwrap();
-    let offers_id: String = get_id(&token_contract_string, &token_id);
-    let offers_purse = get_purse(OFFERS_PURSE);
-
-    let (mut offers, dictionary_uref):
-        (BTreeMap<Key, U512>, URef) = get_offers(&offers_id);
-
-    let amount: U512 = offers.get(&accepted_bidder_hash)
-        .unwrap_or_revert_with(Error::NoMatchingOffer)
-        .clone();    
-
-    system::transfer_from_purse_to_account(
-        offers_purse,
-        seller.into_account().unwrap_or_revert(),
-        amount.clone(),
-        None
-    ).unwrap_or_revert();
-    offers.remove(&accepted_bidder_hash);
-  
-    runtime::call_contract::<()>(
-        token_contract_hash,
-        "transfer_from",
-        runtime_args! {
-            "sender" => seller,
-            "recipient" => accepted_bidder_hash,
-            "token_ids" => token_ids,
-          }
-    );
-
-    // refund the other offers
-    for (account, bid) in &offers {
-        system::transfer_from_purse_to_account(
-            offers_purse,
-            account.into_account().unwrap_or_revert(),
-            bid.clone(),
-            None
-        ).unwrap_or_revert();
-    }
+pub extern "C" fn get_sales_history() -> () {
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
+    let token_id: String = TOKEN_ID_ARG.get();
+
+    let sales = market().get_sales_history(&token_contract_string, &token_id);
+    runtime::ret(CLValue::from_t(sales).unwrap_or_revert())
+}
 
-    offers.clear();
-    force_cancel_listing(&token_contract_string, &token_id);
-    storage::dictionary_put(dictionary_uref, &offers_id, offers);
-
-    emit(&MarketEvent::OfferAccepted {
-        package: contract_package_hash(),
-        seller: seller,
-        buyer: accepted_bidder_hash,
-        token_contract: token_contract_string,
-        token_id: token_id,
-        price: amount
-    })
+#[no_mangle]
+pub extern "C" fn claim() -> () {
+    let claim_purse: Option<URef> = CLAIM_PURSE_ARG.get();
+
+    market().claim(get_immediate_caller(), claim_purse)
+        .unwrap_or_revert()
+}
+
+#[no_mangle]
+pub extern "C" fn get_listing() -> () {
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
+    let token_id: String = TOKEN_ID_ARG.get();
+
+    let listing = market().get_listing(&token_contract_string, &token_id);
+    runtime::ret(CLValue::from_t(listing).unwrap_or_revert())
+}
+
+#[no_mangle]
+pub extern "C" fn get_listing_custody() -> () {
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
+    let token_id: String = TOKEN_ID_ARG.get();
+
+    let custody = market().get_listing_custody(&token_contract_string, &token_id);
+    runtime::ret(CLValue::from_t(custody).unwrap_or_revert())
+}
+
+#[no_mangle]
+pub extern "C" fn get_offers() -> () {
+    let token_contract_string: String = NFT_CONTRACT_HASH_ARG.get();
+    let token_id: String = TOKEN_ID_ARG.get();
+
+    let offers = market().get_offers(&token_contract_string, &token_id);
+    runtime::ret(CLValue::from_t(offers).unwrap_or_revert())
+}
+
+// the package access uref is kept so the installing account can add new versions later
+fn install() -> (ContractPackageHash, NamedKeys) {
+    let (contract_package_hash, access_uref) = storage::create_contract_package_at_hash();
+    runtime::put_key(MARKET_PACKAGE_ACCESS_KEY, access_uref.into());
+
+    let max_offers_per_token: u32 = MAX_OFFERS_PER_TOKEN_ARG.get();
+    let min_offer_amount: U512 = MIN_OFFER_AMOUNT_ARG.get();
+
+    let mut named_keys = NamedKeys::new();
+    init_events(&mut named_keys);
+    named_keys.insert(String::from(MAX_OFFERS_PER_TOKEN), storage::new_uref(max_offers_per_token).into());
+    named_keys.insert(String::from(MIN_OFFER_AMOUNT), storage::new_uref(min_offer_amount).into());
+    named_keys.insert(String::from(REENTRANCY_LOCK), storage::new_uref(false).into());
+    (contract_package_hash, named_keys)
+}
+
+// the engine carries the previous version's named keys (dictionaries, purses, events & config)
+// over to the new one, so nothing is re-initialized & the install args aren't read
+fn upgrade() -> (ContractPackageHash, NamedKeys) {
+    let contract_package_hash = runtime::get_key(MARKET_PACKAGE_HASH_KEY)
+        .and_then(Key::into_hash)
+        .map(ContractPackageHash::new)
+        .unwrap_or_revert_with(ApiError::MissingKey);
+    (contract_package_hash, NamedKeys::new())
 }
 
 #[no_mangle]
 pub extern "C" fn call() {
-    let (contract_package_hash, _) = storage::create_contract_package_at_hash();
+    // markets installed before the access uref was kept can only be installed fresh
+    let previous_contract_hash = runtime::get_key(MARKET_PACKAGE_ACCESS_KEY)
+        .and_then(|_| runtime::get_key(MARKET_CONTRACT_HASH_KEY))
+        .and_then(Key::into_hash)
+        .map(ContractHash::new);
+
+    let (contract_package_hash, named_keys) = match previous_contract_hash {
+        Some(_) => upgrade(),
+        None => install()
+    };
+
     let (contract_hash, _) =
-        storage::add_contract_version(contract_package_hash, get_entry_points(), Default::default());
-    runtime::put_key("market_contract_hash", contract_hash.into());
+        storage::add_contract_version(contract_package_hash, get_entry_points(), named_keys);
+
+    // the old version shares the new one's storage, so it can't be left callable
+    if let Some(previous_contract_hash) = previous_contract_hash {
+        storage::disable_contract_version(contract_package_hash, previous_contract_hash)
+            .unwrap_or_revert();
+    }
+
+    runtime::put_key(MARKET_CONTRACT_HASH_KEY, contract_hash.into());
     let contract_hash_pack = storage::new_uref(contract_hash);
     runtime::put_key("market_contract_hash_wrapped", contract_hash_pack.into());
-    runtime::put_key("market_contract_package_hash", contract_package_hash.into());
-}
-
-fn get_entry_points() -> EntryPoints {
-    let mut entry_points = EntryPoints::new();
-
-    entry_points.add_entry_point(EntryPoint::new(
-        "create_listing",
-        vec![
-            Parameter::new(NFT_CONTRACT_HASH_ARG, String::cl_type()),
-            Parameter::new(TOKEN_ID_ARG, String::cl_type()),
-            Parameter::new(PRICE_ARG, U256::cl_type())
-        ],
-        <()>::cl_type(),
-        EntryPointAccess::Public,
-        EntryPointType::Contract,
-    ));
-    entry_points.add_entry_point(EntryPoint::new(
-        "buy_listing",
-        vec![
-            Parameter::new(NFT_CONTRACT_HASH_ARG, String::cl_type()),
-            Parameter::new(TOKEN_ID_ARG, String::cl_type()),
-            Parameter::new(BUYER_PURSE_ARG, URef::cl_type())
-        ],
-        <()>::cl_type(),
-        EntryPointAccess::Public,
-        EntryPointType::Contract,
-    ));
-    entry_points.add_entry_point(EntryPoint::new(
-        "cancel_listing",
-        vec![
-            Parameter::new(NFT_CONTRACT_HASH_ARG, String::cl_type()),
-            Parameter::new(TOKEN_ID_ARG, String::cl_type())
-        ],
-        <()>::cl_type(),
-        EntryPointAccess::Public,
-        EntryPointType::Contract,
-    ));
-    entry_points.add_entry_point(EntryPoint::new(
-        "make_offer",
-        vec![
-            Parameter::new(NFT_CONTRACT_HASH_ARG, String::cl_type()),
-            Parameter::new(TOKEN_ID_ARG, String::cl_type()),
-            Parameter::new(BUYER_PURSE_ARG, URef::cl_type())
-        ],
-        <()>::cl_type(),
-        EntryPointAccess::Public,
-        EntryPointType::Contract,
-    ));
-    entry_points.add_entry_point(EntryPoint::new(
-        "withdraw_offer",
-        vec![
-            Parameter::new(NFT_CONTRACT_HASH_ARG, String::cl_type()),
-            Parameter::new(TOKEN_ID_ARG, String::cl_type())
-        ],
-        <()>::cl_type(),
-        EntryPointAccess::Public,
-        EntryPointType::Contract,
-    ));
-    entry_points.add_entry_point(EntryPoint::new(
-        "accept_offer",
-        vec![
-            Parameter::new(NFT_CONTRACT_HASH_ARG, String::cl_type()),
-            Parameter::new(TOKEN_ID_ARG, String::cl_type()),
-            Parameter::new(ACCEPTED_OFFER_ARG, URef::cl_type())
-        ],
-        <()>::cl_type(),
-        EntryPointAccess::Public,
-        EntryPointType::Contract,
-    ));
-    entry_points
+    runtime::put_key(MARKET_PACKAGE_HASH_KEY, contract_package_hash.into());
 }
@@ -0,0 +1,154 @@
+//! Arbitrary sequences of entry point calls against `MemoryHost`, with amounts anywhere in U512
+//! so the overflow paths get exercised. Any call may fail, but none may panic & the market's
+//! purses must always hold exactly the open offers & unclaimed balances.
+
+#![no_main]
+
+use casper_types::{account::AccountHash, ContractHash, Key, URef, U256, U512};
+use libfuzzer_sys::{arbitrary::Arbitrary, fuzz_target};
+use market_core::{Market, MemoryHost};
+
+const ACTORS: u8 = 4;
+const TOKENS: u8 = 3;
+
+#[derive(Debug, Arbitrary)]
+enum Op {
+    List { caller: u8, token: u8, price: [u8; 64], approve: bool },
+    Cancel { caller: u8, token: u8 },
+    Buy { buyer: u8, token: u8, amount: [u8; 64] },
+    BuyFloor { buyer: u8, max_price: [u8; 64], amount: [u8; 64] },
+    Offer { bidder: u8, token: u8, amount: [u8; 64] },
+    Withdraw { bidder: u8, token: u8 },
+    Accept { caller: u8, token: u8, bidder: u8 },
+    Claim { caller: u8, to_purse: bool }
+}
+
+// the last actor is a contract, so claims & payouts to contracts are covered too
+fn actor(index: u8) -> Key {
+    match index % ACTORS {
+        index if index == ACTORS - 1 => Key::Hash([index + 1; 32]),
+        index => Key::Account(AccountHash::new([index + 1; 32]))
+    }
+}
+
+fn token(index: u8) -> String {
+    (index % TOKENS).to_string()
+}
+
+fn token_contract() -> ContractHash {
+    ContractHash::new([0xbb; 32])
+}
+
+fn amount(bytes: &[u8; 64]) -> U512 {
+    U512::from_little_endian(bytes)
+}
+
+struct Run {
+    market: Market<MemoryHost>,
+    token_arg: String,
+    purses: Vec<URef>,
+    // None once the motes handed out no longer fit in a U512
+    minted: Option<U512>
+}
+
+impl Run {
+    fn new_purse(&mut self, amount: U512) -> URef {
+        let purse = self.market.host_mut().new_purse(amount);
+        self.purses.push(purse);
+        self.minted = self.minted.and_then(|minted| minted.checked_add(amount));
+        purse
+    }
+
+    fn apply(&mut self, op: &Op) -> () {
+        let token_arg = self.token_arg.clone();
+        let _ = match *op {
+            Op::List { caller, token: index, ref price, approve } => {
+                let caller = actor(caller);
+                let id = U256::from(index % TOKENS);
+                if approve && self.market.host().owner(token_contract(), id) == Some(caller) {
+                    let host = self.market.host_mut();
+                    let package = host.market_package_key();
+                    host.approve(token_contract(), id, package);
+                }
+                let price = amount(price);
+                self.market.execute(|market| market.create_listing(caller, &token_arg, &token(index), price))
+            },
+            Op::Cancel { caller, token: index } => {
+                self.market.execute(|market| market.cancel_listing(actor(caller), &token_arg, &token(index)))
+            },
+            Op::Buy { buyer, token: index, amount: ref funds } => {
+                let purse = self.new_purse(amount(funds));
+                self.market.execute(|market| market.buy_listing(actor(buyer), &token_arg, &token(index), purse))
+            },
+            Op::BuyFloor { buyer, ref max_price, amount: ref funds } => {
+                let (max_price, purse) = (amount(max_price), self.new_purse(amount(funds)));
+                self.market.execute(|market| market.buy_floor(actor(buyer), &token_arg, max_price, purse))
+            },
+            Op::Offer { bidder, token: index, amount: ref funds } => {
+                let purse = self.new_purse(amount(funds));
+                self.market.execute(|market| market.make_offer(actor(bidder), &token_arg, &token(index), purse))
+            },
+            Op::Withdraw { bidder, token: index } => {
+                self.market.execute(|market| market.withdraw_offer(actor(bidder), &token_arg, &token(index)))
+            },
+            Op::Accept { caller, token: index, bidder } => {
+                let accepted = actor(bidder).to_formatted_string();
+                self.market.execute(|market| market.accept_offer(actor(caller), &token_arg, &token(index), &accepted))
+            },
+            Op::Claim { caller, to_purse } => {
+                let purse = if to_purse { Some(self.new_purse(U512::zero())) } else { None };
+                self.market.execute(|market| market.claim(actor(caller), purse))
+            }
+        };
+    }
+
+    fn check(&mut self) -> () {
+        let mut escrowed = U512::zero();
+        for index in 0..TOKENS {
+            for amount in self.market.get_offers(&self.token_arg, &token(index)).values() {
+                escrowed += *amount;
+            }
+        }
+        assert_eq!(self.market.host().offers_purse_balance(), escrowed, "offers purse doesn't match the open offers");
+
+        let mut claimable = U512::zero();
+        for index in 0..ACTORS {
+            claimable += self.market.get_claim(&actor(index));
+        }
+        assert_eq!(self.market.host().claims_purse_balance(), claimable, "claims purse doesn't match the claims");
+
+        let minted = match self.minted {
+            Some(minted) => minted,
+            None => return
+        };
+        let host = self.market.host();
+        let mut total = host.offers_purse_balance() + host.claims_purse_balance();
+        for purse in &self.purses {
+            total += host.purse_balance(*purse);
+        }
+        for index in 0..ACTORS {
+            if let Key::Account(account) = actor(index) {
+                total += host.account_balance(account);
+            }
+        }
+        assert_eq!(total, minted, "motes were created or lost");
+    }
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    let mut market = Market::new(MemoryHost::new(3, U512::one()));
+    for index in 0..TOKENS {
+        market.host_mut().mint(token_contract(), U256::from(index), actor(index));
+    }
+    let mut run = Run {
+        market,
+        token_arg: token_contract().to_formatted_string(),
+        purses: Vec::new(),
+        minted: Some(U512::zero())
+    };
+
+    for op in &ops {
+        run.apply(op);
+        run.check();
+    }
+});
@@ -0,0 +1,75 @@
+//! Arbitrary strings & amounts through the arg parsing `create_listing`, `buy_listing`,
+//! `make_offer` & `accept_offer` start with, then through those entry points themselves.
+//! Anything may be rejected, nothing may panic.
+
+#![no_main]
+
+use casper_types::{account::AccountHash, Key, U512};
+use libfuzzer_sys::{arbitrary::Arbitrary, fuzz_target};
+use market_core::{
+    parse_account_key, parse_token_contract_hash, parse_token_id, token_id_to_vec, Market, MemoryHost};
+
+#[derive(Debug, Arbitrary)]
+struct Args {
+    token_contract: String,
+    token_id: String,
+    accepted_offer: String,
+    price: [u8; 64],
+    buyer_amount: [u8; 64],
+    offer_amount: [u8; 64]
+}
+
+fn amount(bytes: &[u8; 64]) -> U512 {
+    U512::from_little_endian(bytes)
+}
+
+fuzz_target!(|args: Args| {
+    let contract = parse_token_contract_hash(&args.token_contract);
+    if let Ok(hash) = contract {
+        assert_eq!(parse_token_contract_hash(&hash.to_formatted_string()), Ok(hash));
+    }
+    let token_id = parse_token_id(&args.token_id);
+    assert_eq!(token_id_to_vec(&args.token_id), token_id.map(|id| vec![id]));
+    let _ = parse_account_key(&args.accepted_offer);
+
+    let (seller, buyer, bidder) = (
+        Key::Account(AccountHash::new([1; 32])),
+        Key::Account(AccountHash::new([2; 32])),
+        Key::Account(AccountHash::new([3; 32]))
+    );
+    let mut market = Market::new(MemoryHost::new(3, U512::one()));
+
+    // give the seller the token when the args name one, so listing gets past the owner checks
+    if let (Ok(hash), Ok(id)) = (contract, token_id) {
+        let host = market.host_mut();
+        host.mint(hash, id, seller);
+        let package = host.market_package_key();
+        host.approve(hash, id, package);
+    }
+
+    let price = amount(&args.price);
+    let listed = market.execute(|market| market.create_listing(seller, &args.token_contract, &args.token_id, price));
+    if listed.is_ok() {
+        let listing = market.get_listing(&args.token_contract, &args.token_id);
+        assert_eq!(listing.map(|listing| listing.price), Some(price));
+    }
+
+    let buyer_amount = amount(&args.buyer_amount);
+    let buyer_purse = market.host_mut().new_purse(buyer_amount);
+    if market.execute(|market| market.buy_listing(buyer, &args.token_contract, &args.token_id, buyer_purse)).is_ok() {
+        assert_eq!(market.host().purse_balance(buyer_purse), buyer_amount - price);
+    }
+
+    let offer_amount = amount(&args.offer_amount);
+    let offer_purse = market.host_mut().new_purse(offer_amount);
+    if market.execute(|market| market.make_offer(bidder, &args.token_contract, &args.token_id, offer_purse)).is_ok() {
+        assert_eq!(market.host().offers_purse_balance(), offer_amount);
+    }
+
+    // whoever holds the token now accepts whichever offer the arg names
+    let owner = contract.ok()
+        .zip(token_id.ok())
+        .and_then(|(hash, id)| market.host().owner(hash, id))
+        .unwrap_or(seller);
+    let _ = market.execute(|market| market.accept_offer(owner, &args.token_contract, &args.token_id, &args.accepted_offer));
+});
@@ -0,0 +1,53 @@
+use casper_types::ApiError;
+
+// the variants & their codes are listed once, `from_code` is generated from the same list
+macro_rules! market_errors {
+    ($($name:ident = $code:literal),* $(,)?) => {
+        /// An error enum which can be converted to a `u16` so it can be returned as an `ApiError::User`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[repr(u16)]
+        pub enum Error {
+            $($name = $code),*
+        }
+
+        impl Error {
+            /// The error an `ApiError::User` code stands for, `None` for codes the market doesn't use.
+            pub fn from_code(code: u16) -> Option<Error> {
+                match code {
+                    $($code => Some(Error::$name),)*
+                    _ => None
+                }
+            }
+        }
+    };
+}
+
+market_errors! {
+    ListingDoesNotExist = 1000,
+    ListingCanceledOrSold = 1001,
+    BalanceInsufficient = 1002,
+    PermissionDenied = 1003,
+    NoMatchingOffer = 1004,
+    OfferExists = 1005,
+    OfferPurseRetrieval = 1006,
+    NeedsTransferApproval = 1007,
+    InvalidTokenContractHash = 1008,
+    InvalidTokenId = 1009,
+    InvalidAccountKey = 1010,
+    MissingApprovalResult = 1011,
+    ArithmeticOverflow = 1012,
+    InvalidPurse = 1013,
+    NothingToClaim = 1014,
+    OfferTooLow = 1015,
+    TooManyOffers = 1016,
+    NoActiveListings = 1017,
+    FloorAboveMaxPrice = 1018,
+    TokenNotTransferred = 1019,
+    ReentrantCall = 1020
+}
+
+impl From<Error> for ApiError {
+    fn from(error: Error) -> Self {
+        ApiError::User(error as u16)
+    }
+}
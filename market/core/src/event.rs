@@ -0,0 +1,254 @@
+use alloc::{
+    string::String,
+    vec, vec::Vec,
+    collections::BTreeMap
+};
+use casper_types::{
+    bytesrepr::{self, FromBytes, ToBytes},
+    CLType, CLTyped, ContractHash, Key, U512};
+
+// Casper Event Standard (CES) named keys
+pub const EVENTS_DICT: &str = "__events";
+pub const EVENTS_LENGTH: &str = "__events_length";
+pub const EVENTS_SCHEMA: &str = "__events_schema";
+pub const CES_VERSION_KEY: &str = "__events_ces_version";
+pub const CES_VERSION: &str = "0.1.0";
+
+// stored ahead of each event's name
+pub const EVENT_PREFIX: &str = "event_";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarketEvent {
+    ListingCreated {
+        seller: Key, //Key vs AccountHash so we know what we're getting client side
+        token_contract: ContractHash,
+        token_id: String,
+        price: U512
+    },
+    ListingPurchased {
+        seller: Key,
+        buyer: Key,
+        token_contract: ContractHash,
+        token_id: String,
+        price: U512
+    },
+    ListingCanceled {
+        token_contract: ContractHash,
+        token_id: String
+    },
+    OfferCreated {
+        buyer: Key,
+        token_contract: ContractHash,
+        token_id: String,
+        price: U512
+    },
+    OfferWithdraw {
+        buyer: Key,
+        token_contract: ContractHash,
+        token_id: String
+    },
+    OfferAccepted {
+        seller: Key,
+        buyer: Key,
+        token_contract: ContractHash,
+        token_id: String,
+        price: U512
+    },
+}
+
+impl MarketEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MarketEvent::ListingCreated { .. } => "ListingCreated",
+            MarketEvent::ListingPurchased { .. } => "ListingPurchased",
+            MarketEvent::ListingCanceled { .. } => "ListingCanceled",
+            MarketEvent::OfferCreated { .. } => "OfferCreated",
+            MarketEvent::OfferWithdraw { .. } => "OfferWithdraw",
+            MarketEvent::OfferAccepted { .. } => "OfferAccepted",
+        }
+    }
+
+    // fields in the same order as they're declared in `schemas`
+    fn fields(&self) -> Result<Vec<Vec<u8>>, bytesrepr::Error> {
+        let fields = match self {
+            MarketEvent::ListingCreated { seller, token_contract, token_id, price } =>
+                vec![seller.to_bytes()?, token_contract.to_bytes()?, token_id.to_bytes()?, price.to_bytes()?],
+            MarketEvent::ListingPurchased { seller, buyer, token_contract, token_id, price } =>
+                vec![seller.to_bytes()?, buyer.to_bytes()?, token_contract.to_bytes()?, token_id.to_bytes()?, price.to_bytes()?],
+            MarketEvent::ListingCanceled { token_contract, token_id } =>
+                vec![token_contract.to_bytes()?, token_id.to_bytes()?],
+            MarketEvent::OfferCreated { buyer, token_contract, token_id, price } =>
+                vec![buyer.to_bytes()?, token_contract.to_bytes()?, token_id.to_bytes()?, price.to_bytes()?],
+            MarketEvent::OfferWithdraw { buyer, token_contract, token_id } =>
+                vec![buyer.to_bytes()?, token_contract.to_bytes()?, token_id.to_bytes()?],
+            MarketEvent::OfferAccepted { seller, buyer, token_contract, token_id, price } =>
+                vec![seller.to_bytes()?, buyer.to_bytes()?, token_contract.to_bytes()?, token_id.to_bytes()?, price.to_bytes()?],
+        };
+        Ok(fields)
+    }
+}
+
+impl ToBytes for MarketEvent {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        let mut name = String::from(EVENT_PREFIX);
+        name.push_str(self.name());
+
+        let mut bytes = name.to_bytes()?;
+        for mut field in self.fields()? {
+            bytes.append(&mut field);
+        }
+        Ok(bytes)
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.to_bytes().map(|bytes| bytes.len()).unwrap_or_default()
+    }
+}
+
+impl FromBytes for MarketEvent {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), bytesrepr::Error> {
+        let (name, bytes) = String::from_bytes(bytes)?;
+
+        let mut fields = Fields(bytes);
+        let event = match name.strip_prefix(EVENT_PREFIX) {
+            Some("ListingCreated") => MarketEvent::ListingCreated {
+                seller: fields.next()?,
+                token_contract: fields.next()?,
+                token_id: fields.next()?,
+                price: fields.next()?
+            },
+            Some("ListingPurchased") => MarketEvent::ListingPurchased {
+                seller: fields.next()?,
+                buyer: fields.next()?,
+                token_contract: fields.next()?,
+                token_id: fields.next()?,
+                price: fields.next()?
+            },
+            Some("ListingCanceled") => MarketEvent::ListingCanceled {
+                token_contract: fields.next()?,
+                token_id: fields.next()?
+            },
+            Some("OfferCreated") => MarketEvent::OfferCreated {
+                buyer: fields.next()?,
+                token_contract: fields.next()?,
+                token_id: fields.next()?,
+                price: fields.next()?
+            },
+            Some("OfferWithdraw") => MarketEvent::OfferWithdraw {
+                buyer: fields.next()?,
+                token_contract: fields.next()?,
+                token_id: fields.next()?
+            },
+            Some("OfferAccepted") => MarketEvent::OfferAccepted {
+                seller: fields.next()?,
+                buyer: fields.next()?,
+                token_contract: fields.next()?,
+                token_id: fields.next()?,
+                price: fields.next()?
+            },
+            // not one of the market's events, `schemas` names them all
+            _ => return Err(bytesrepr::Error::Formatting)
+        };
+        Ok((event, fields.0))
+    }
+}
+
+// reads the event fields in schema order
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn next<T: FromBytes>(&mut self) -> Result<T, bytesrepr::Error> {
+        let (value, remainder) = T::from_bytes(self.0)?;
+        self.0 = remainder;
+        Ok(value)
+    }
+}
+
+pub struct Schema(Vec<(String, CLType)>);
+
+impl Schema {
+    fn new(fields: &[(&str, CLType)]) -> Self {
+        Schema(fields.iter().map(|(name, cl_type)| (String::from(*name), cl_type.clone())).collect())
+    }
+}
+
+impl CLTyped for Schema {
+    fn cl_type() -> CLType {
+        CLType::Any
+    }
+}
+
+impl ToBytes for Schema {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        self.0.to_bytes()
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.0.serialized_length()
+    }
+}
+
+pub struct Schemas(BTreeMap<String, Schema>);
+
+impl Schemas {
+    /// Whether `name`, without the `event_` prefix, is one of the market's events.
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+}
+
+impl CLTyped for Schemas {
+    fn cl_type() -> CLType {
+        BTreeMap::<String, Schema>::cl_type()
+    }
+}
+
+impl ToBytes for Schemas {
+    fn to_bytes(&self) -> Result<Vec<u8>, bytesrepr::Error> {
+        self.0.to_bytes()
+    }
+
+    fn serialized_length(&self) -> usize {
+        self.0.serialized_length()
+    }
+}
+
+pub fn schemas() -> Schemas {
+    let mut schemas = BTreeMap::new();
+    schemas.insert(String::from("ListingCreated"), Schema::new(&[
+        ("seller", Key::cl_type()),
+        ("token_contract", ContractHash::cl_type()),
+        ("token_id", String::cl_type()),
+        ("price", U512::cl_type())
+    ]));
+    schemas.insert(String::from("ListingPurchased"), Schema::new(&[
+        ("seller", Key::cl_type()),
+        ("buyer", Key::cl_type()),
+        ("token_contract", ContractHash::cl_type()),
+        ("token_id", String::cl_type()),
+        ("price", U512::cl_type())
+    ]));
+    schemas.insert(String::from("ListingCanceled"), Schema::new(&[
+        ("token_contract", ContractHash::cl_type()),
+        ("token_id", String::cl_type())
+    ]));
+    schemas.insert(String::from("OfferCreated"), Schema::new(&[
+        ("buyer", Key::cl_type()),
+        ("token_contract", ContractHash::cl_type()),
+        ("token_id", String::cl_type()),
+        ("price", U512::cl_type())
+    ]));
+    schemas.insert(String::from("OfferWithdraw"), Schema::new(&[
+        ("buyer", Key::cl_type()),
+        ("token_contract", ContractHash::cl_type()),
+        ("token_id", String::cl_type())
+    ]));
+    schemas.insert(String::from("OfferAccepted"), Schema::new(&[
+        ("seller", Key::cl_type()),
+        ("buyer", Key::cl_type()),
+        ("token_contract", ContractHash::cl_type()),
+        ("token_id", String::cl_type()),
+        ("price", U512::cl_type())
+    ]));
+    Schemas(schemas)
+}
@@ -0,0 +1,64 @@
+use alloc::vec::Vec;
+
+use casper_types::{
+    account::AccountHash,
+    bytesrepr::{FromBytes, ToBytes},
+    ApiError, CLTyped, ContractHash, ContractPackageHash, Key, URef, U256, U512};
+
+use crate::event::MarketEvent;
+
+/// A purse the market moves motes between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Purse {
+    /// Handed in by the caller, e.g. the payment wasm's purse or a claim target.
+    External(URef),
+    /// Escrows every outstanding offer.
+    Offers,
+    /// Holds proceeds & refunds until their owners claim them.
+    Claims
+}
+
+/// Everything the market needs from the chain: its own storage, the token contracts it trades &
+/// purses. Failures the market doesn't handle itself, like a token contract refusing a transfer,
+/// come back as the `ApiError` the contract reverts with.
+pub trait MarketHost {
+    /// `None` when the item is missing or holds another type.
+    fn dictionary_get<T: CLTyped + FromBytes>(&mut self, dictionary: &str, key: &str) -> Option<T>;
+
+    fn dictionary_put<T: CLTyped + ToBytes>(&mut self, dictionary: &str, key: &str, value: T) -> ();
+
+    /// Install time config stored under a named key.
+    fn named_value<T: CLTyped + FromBytes>(&mut self, name: &str) -> Option<T>;
+
+    /// Runtime state under a named key, the key is created on first write.
+    fn put_named_value<T: CLTyped + ToBytes>(&mut self, name: &str, value: T) -> ();
+
+    fn blake2b(&self, bytes: &[u8]) -> [u8; 32];
+
+    /// Block time in milliseconds.
+    fn block_time(&self) -> u64;
+
+    fn emit(&mut self, event: MarketEvent) -> ();
+
+    /// The market's package hash, the identity token contracts see & approvals name.
+    fn market_package(&self) -> ContractPackageHash;
+
+    fn owner_of(&mut self, token_contract: ContractHash, token_id: U256) -> Option<Key>;
+
+    fn get_approved(&mut self, token_contract: ContractHash, owner: Key, token_id: U256) -> Option<Key>;
+
+    fn transfer_from(
+        &mut self,
+        token_contract: ContractHash,
+        sender: Key,
+        recipient: Key,
+        token_ids: Vec<U256>
+    ) -> Result<(), ApiError>;
+
+    /// `None` when `purse` isn't a purse.
+    fn balance(&mut self, purse: Purse) -> Option<U512>;
+
+    fn transfer_to_purse(&mut self, source: Purse, target: Purse, amount: U512) -> Result<(), ApiError>;
+
+    fn transfer_to_account(&mut self, source: Purse, target: AccountHash, amount: U512) -> Result<(), ApiError>;
+}
@@ -0,0 +1,31 @@
+//! The market's rules without the chain: listings, offers, claims, collection stats & the ids
+//! they're stored under. `Market` runs every entry point over a `MarketHost`, which the contract
+//! implements with `casper_contract` & tests with `MemoryHost` (the `test-support` feature), so
+//! the same code is checked natively & shipped in the wasm.
+
+#![no_std]
+
+extern crate alloc;
+
+mod error;
+mod event;
+mod host;
+mod market;
+#[cfg(feature = "test-support")]
+mod memory_host;
+mod types;
+
+pub use error::Error;
+pub use event::{
+    schemas, MarketEvent, Schemas, CES_VERSION, CES_VERSION_KEY, EVENTS_DICT, EVENTS_LENGTH, EVENTS_SCHEMA,
+    EVENT_PREFIX
+};
+pub use host::{MarketHost, Purse};
+pub use market::{
+    get_contract_id, parse_account_key, parse_token_contract_hash, parse_token_id, token_id_to_vec,
+    Market, ACCOUNT_LISTINGS_DICTIONARY, ACCOUNT_OFFERS_DICTIONARY, MAX_OFFERS_PER_TOKEN, MIN_OFFER_AMOUNT,
+    REENTRANCY_LOCK
+};
+#[cfg(feature = "test-support")]
+pub use memory_host::MemoryHost;
+pub use types::{CollectionStats, Listing, Sale, SALE_TYPE_LISTING, SALE_TYPE_OFFER};
@@ -0,0 +1,951 @@
+use alloc::{
+    string::String,
+    vec, vec::Vec,
+    collections::BTreeMap
+};
+
+use casper_types::{
+    bytesrepr::{FromBytes, ToBytes},
+    ApiError, CLTyped, ContractHash, Key, URef, U256, U512};
+
+use crate::{
+    error::Error,
+    event::MarketEvent,
+    host::{MarketHost, Purse},
+    types::{CollectionStats, Listing, Sale, SALE_TYPE_LISTING, SALE_TYPE_OFFER}
+};
+
+const LISTING_DICTIONARY: &str = "listings";
+const OFFER_DICTIONARY: &str = "offers";
+const OFFER_INDEX_DICTIONARY: &str = "offer_index";
+const CLAIM_DICTIONARY: &str = "claims";
+// true for listings whose token the market holds, keyed like listings
+const CUSTODY_DICTIONARY: &str = "custody_listings";
+
+const COLLECTION_STATS_DICTIONARY: &str = "collection_stats";
+// each collection's active listings as a binary min-heap on price, then listing order, one entry
+// per slot so opening or closing a listing rewrites O(log n) entries instead of all of them
+const FLOOR_DICTIONARY: &str = "floor_listings";
+// the slot each listed token sits at, None once it's off the floor
+const FLOOR_SLOT_DICTIONARY: &str = "floor_slots";
+// heap size & the next listing's sequence number per collection, keyed by the contract id
+const FLOOR_COUNTERS_DICTIONARY: &str = "floor_counters";
+// price, sequence number & token id of a listing on the floor
+type FloorEntry = (U512, u64, String);
+
+const SALES_HISTORY_DICTIONARY: &str = "sales_history";
+
+// stale listings `buy_floor` closes before it gives up, each costs a few reads & writes
+const MAX_FLOOR_ATTEMPTS: usize = 8;
+
+// only the most recent sales of a token are kept
+const MAX_SALES_HISTORY: usize = 20;
+
+// the listing & offer ids each account has open. an account's count sits under its `get_key_id`,
+// its ids under `get_id(account id, index)` & each id's index under `get_id(account id, id)`, so
+// opening or closing a position writes a fixed number of entries however many the account has
+pub const ACCOUNT_LISTINGS_DICTIONARY: &str = "account_listings";
+pub const ACCOUNT_OFFERS_DICTIONARY: &str = "account_offers";
+
+// install time config, each under its own named key
+pub const MAX_OFFERS_PER_TOKEN: &str = "max_offers_per_token";
+pub const MIN_OFFER_AMOUNT: &str = "min_offer_amount";
+
+// set while an entry point runs, see `Market::locked`
+pub const REENTRANCY_LOCK: &str = "reentrancy_lock";
+
+pub fn parse_token_contract_hash(token_contract: &str) -> Result<ContractHash, Error> {
+    ContractHash::from_formatted_str(token_contract)
+        .map_err(|_| Error::InvalidTokenContractHash)
+}
+
+pub fn parse_token_id(token_id: &str) -> Result<U256, Error> {
+    U256::from_dec_str(token_id)
+        .map_err(|_| Error::InvalidTokenId)
+}
+
+pub fn parse_account_key(account: &str) -> Result<Key, Error> {
+    Key::from_formatted_str(account)
+        .map_err(|_| Error::InvalidAccountKey)
+}
+
+pub fn token_id_to_vec(token_id: &str) -> Result<Vec<U256>, Error> {
+    Ok(vec![parse_token_id(token_id)?])
+}
+
+// ids are hashed from the args as strings, so args that only differ in hex case or leading zeros
+// are brought to one form first or they'd name different listings of the same token
+fn canonical_token(token_contract: &str, token_id: &str) -> Result<(String, String), Error> {
+    let token_contract_hash = parse_token_contract_hash(token_contract)?;
+    let token_id = parse_token_id(token_id)?;
+    Ok((token_contract_hash.to_formatted_string(), token_id.to_string()))
+}
+
+pub fn get_contract_id(token_contract: &ContractHash) -> String {
+    hex::encode(token_contract.value())
+}
+
+// strings & keys only fail to serialize past u32::MAX bytes, far more than a deploy carries
+fn serialize<T: ToBytes>(value: &T) -> Vec<u8> {
+    value.to_bytes().unwrap_or_default()
+}
+
+/// Every market entry point over a `MarketHost`. `caller` args are whoever called the entry
+/// point, an account or a contract package. Errors are the `ApiError` the contract reverts with,
+/// the market's own are `Error`s as `ApiError::User`. Like a reverted deploy, nothing written
+/// before an error is meant to be kept, the host has to discard it. Entry points that write
+/// can't be re-entered, a nested call fails with `Error::ReentrantCall`.
+pub struct Market<H> {
+    host: H
+}
+
+impl<H: MarketHost> Market<H> {
+    pub fn new(host: H) -> Self {
+        Market { host }
+    }
+
+    pub fn host(&self) -> &H {
+        &self.host
+    }
+
+    pub fn host_mut(&mut self) -> &mut H {
+        &mut self.host
+    }
+
+    pub fn get_id(&self, token_contract: &str, token_id: &str) -> String {
+        let mut bytes = serialize(&String::from(token_contract));
+        bytes.append(&mut serialize(&String::from(token_id)));
+        hex::encode(self.host.blake2b(&bytes))
+    }
+
+    // dictionary keys are limited to 64 chars so formatted keys can't be used directly
+    pub fn get_key_id(&self, key: &Key) -> String {
+        hex::encode(self.host.blake2b(&serialize(key)))
+    }
+
+    pub fn create_listing(
+        &mut self,
+        token_owner: Key,
+        token_contract_string: &str,
+        token_id: &str,
+        price: U512
+    ) -> Result<(), ApiError> {
+        self.locked(|market| {
+            let (token_contract, token_id) = canonical_token(token_contract_string, token_id)?;
+            market.open_listing(token_owner, &token_contract, &token_id, price, false)
+        })
+    }
+
+    /// Lists like `create_listing` but moves the token to the market until the listing closes, so
+    /// the seller can't move it away & the listing stays fillable. Canceling hands it back.
+    pub fn create_custody_listing(
+        &mut self,
+        token_owner: Key,
+        token_contract_string: &str,
+        token_id: &str,
+        price: U512
+    ) -> Result<(), ApiError> {
+        self.locked(|market| {
+            let (token_contract, token_id) = canonical_token(token_contract_string, token_id)?;
+            market.open_listing(token_owner, &token_contract, &token_id, price, true)
+        })
+    }
+
+    pub fn buy_listing(
+        &mut self,
+        buyer: Key,
+        token_contract_string: &str,
+        token_id: &str,
+        buyer_purse: URef
+    ) -> Result<(), ApiError> {
+        self.locked(|market| {
+            let (token_contract, token_id) = canonical_token(token_contract_string, token_id)?;
+            market.purchase_listing(buyer, &token_contract, &token_id, buyer_purse)
+        })
+    }
+
+    /// Buys the collection's cheapest listing if it costs at most `max_price`. Listings whose
+    /// token moved or lost the market's approval are closed on the way, up to
+    /// `MAX_FLOOR_ATTEMPTS` of them; if that's all it finds it returns without buying anything.
+    pub fn buy_floor(
+        &mut self,
+        buyer: Key,
+        token_contract_string: &str,
+        max_price: U512,
+        buyer_purse: URef
+    ) -> Result<(), ApiError> {
+        self.locked(|market| {
+            let token_contract = parse_token_contract_hash(token_contract_string)?.to_formatted_string();
+            market.purchase_floor(buyer, &token_contract, max_price, buyer_purse)
+        })
+    }
+
+    pub fn cancel_listing(&mut self, caller: Key, token_contract_string: &str, token_id: &str) -> Result<(), ApiError> {
+        self.locked(|market| {
+            let (token_contract, token_id) = canonical_token(token_contract_string, token_id)?;
+            market.close_listing(caller, &token_contract, &token_id)
+        })
+    }
+
+    pub fn make_offer(
+        &mut self,
+        bidder: Key,
+        token_contract_string: &str,
+        token_id: &str,
+        bidder_purse: URef
+    ) -> Result<(), ApiError> {
+        self.locked(|market| {
+            let (token_contract, token_id) = canonical_token(token_contract_string, token_id)?;
+            market.place_offer(bidder, &token_contract, &token_id, bidder_purse)
+        })
+    }
+
+    pub fn withdraw_offer(&mut self, bidder: Key, token_contract_string: &str, token_id: &str) -> Result<(), ApiError> {
+        self.locked(|market| {
+            let (token_contract, token_id) = canonical_token(token_contract_string, token_id)?;
+            market.refund_offer(bidder, &token_contract, &token_id)
+        })
+    }
+
+    pub fn accept_offer(
+        &mut self,
+        seller: Key,
+        token_contract_string: &str,
+        token_id: &str,
+        accepted_offer: &str
+    ) -> Result<(), ApiError> {
+        self.locked(|market| {
+            let (token_contract, token_id) = canonical_token(token_contract_string, token_id)?;
+            market.settle_offer(seller, &token_contract, &token_id, accepted_offer)
+        })
+    }
+
+    /// Accounts may leave out `claim_purse` & get paid to their main purse, contracts have none.
+    pub fn claim(&mut self, claimant: Key, claim_purse: Option<URef>) -> Result<(), ApiError> {
+        self.locked(|market| market.pay_claim(claimant, claim_purse))
+    }
+
+    // token contracts are called partway through an entry point, the lock keeps one from calling
+    // back in while storage is half updated. a reverted deploy drops the lock with everything
+    // else, it's still released on errors so a host that keeps failed writes isn't left locked
+    fn locked<T, F>(&mut self, entry_point: F) -> Result<T, ApiError>
+    where
+        F: FnOnce(&mut Self) -> Result<T, ApiError>
+    {
+        if self.host.named_value::<bool>(REENTRANCY_LOCK).unwrap_or_default() {
+            return Err(Error::ReentrantCall.into());
+        }
+        self.host.put_named_value(REENTRANCY_LOCK, true);
+        let result = entry_point(self);
+        self.host.put_named_value(REENTRANCY_LOCK, false);
+        result
+    }
+
+    fn open_listing(
+        &mut self,
+        token_owner: Key,
+        token_contract_string: &str,
+        token_id: &str,
+        price: U512,
+        custody: bool
+    ) -> Result<(), ApiError> {
+        let token_contract_hash = parse_token_contract_hash(token_contract_string)?;
+
+        if token_owner != self.get_token_owner(token_contract_hash, token_id)? {
+            return Err(Error::PermissionDenied.into());
+        }
+
+        if !self.transfer_approved(token_contract_hash, token_id, token_owner)? {
+            return Err(Error::NeedsTransferApproval.into());
+        }
+
+        let listing = Listing {
+            token_contract: token_contract_hash,
+            token_id: String::from(token_id),
+            price,
+            seller: token_owner
+        };
+
+        let listing_id = self.get_id(token_contract_string, token_id);
+
+        // a listing left behind by a previous owner no longer belongs to them
+        match self.find_listing(&listing_id) {
+            Some(previous) => self.untrack_position(ACCOUNT_LISTINGS_DICTIONARY, &previous.seller, &listing_id),
+            None => self.update_collection_stats(&token_contract_hash, |stats| stats.listing_opened())?
+        }
+
+        self.host.dictionary_put(LISTING_DICTIONARY, &listing_id, listing);
+        // only custody is recorded, a listing in custody is never replaced as the market owns its token
+        if custody {
+            self.host.dictionary_put(CUSTODY_DICTIONARY, &listing_id, true);
+        }
+        self.track_position(ACCOUNT_LISTINGS_DICTIONARY, &token_owner, &listing_id);
+        self.floor_insert(&token_contract_hash, token_id, price);
+
+        self.host.emit(MarketEvent::ListingCreated {
+            seller: token_owner,
+            token_contract: token_contract_hash,
+            token_id: String::from(token_id),
+            price
+        });
+
+        if custody {
+            let market = self.market_key();
+            self.host.transfer_from(token_contract_hash, token_owner, market, token_id_to_vec(token_id)?)?;
+            self.verify_token_owner(token_contract_hash, token_id, market)?;
+        }
+        Ok(())
+    }
+
+    fn purchase_floor(
+        &mut self,
+        buyer: Key,
+        token_contract_string: &str,
+        max_price: U512,
+        buyer_purse: URef
+    ) -> Result<(), ApiError> {
+        let token_contract_hash = parse_token_contract_hash(token_contract_string)?;
+
+        // listings the market can no longer fill are closed so they stop holding up the floor &
+        // the next cheapest is tried. a failed call would reopen them, so once one is closed the
+        // call succeeds even if nothing is bought
+        for attempt in 0..MAX_FLOOR_ATTEMPTS {
+            let token_id = match self.floor_peek(&token_contract_hash) {
+                Some((price, token_id)) if price <= max_price => token_id,
+                _ if attempt > 0 => return Ok(()),
+                Some(_) => return Err(Error::FloorAboveMaxPrice.into()),
+                None => return Err(Error::NoActiveListings.into())
+            };
+
+            if !self.close_stale_listing(token_contract_string, &token_id)? {
+                return self.purchase_listing(buyer, token_contract_string, &token_id, buyer_purse);
+            }
+        }
+        Ok(())
+    }
+
+    fn purchase_listing(
+        &mut self,
+        buyer: Key,
+        token_contract_string: &str,
+        token_id: &str,
+        buyer_purse: URef
+    ) -> Result<(), ApiError> {
+        let token_contract_hash = parse_token_contract_hash(token_contract_string)?;
+        let token_ids = token_id_to_vec(token_id)?;
+        let listing_id = self.get_id(token_contract_string, token_id);
+        let listing = self.get_active_listing(&listing_id)?;
+        let purse_balance = self.host.balance(Purse::External(buyer_purse))
+            .ok_or(Error::InvalidPurse)?;
+
+        if purse_balance < listing.price {
+            return Err(Error::BalanceInsufficient.into());
+        }
+
+        // a token in custody is the market's, the listing knows who it's selling for
+        let custody = self.in_custody(&listing_id);
+        let (seller, holder) = if custody {
+            (listing.seller, self.market_key())
+        } else {
+            let owner = self.get_token_owner(token_contract_hash, token_id)?;
+            (owner, owner)
+        };
+
+        // checks-effects-interactions: the listing is closed before the seller is paid or the
+        // token contract runs, so nothing they call back into can see it open
+        self.host.dictionary_put(LISTING_DICTIONARY, &listing_id, None::<Listing>);
+        if custody {
+            self.host.dictionary_put(CUSTODY_DICTIONARY, &listing_id, false);
+        }
+        self.untrack_position(ACCOUNT_LISTINGS_DICTIONARY, &listing.seller, &listing_id);
+        self.floor_remove(&token_contract_hash, token_id);
+        self.update_collection_stats(&token_contract_hash, |stats| {
+            stats.listing_closed();
+            stats.record_sale(listing.price)
+        })?;
+        self.record_sale(&listing_id, listing.price, seller, buyer, SALE_TYPE_LISTING);
+
+        self.host.emit(MarketEvent::ListingPurchased {
+            seller,
+            buyer,
+            token_contract: token_contract_hash,
+            token_id: String::from(token_id),
+            price: listing.price
+        });
+
+        self.pay_out(Purse::External(buyer_purse), seller, listing.price)?;
+        self.host.transfer_from(token_contract_hash, holder, buyer, token_ids)?;
+        self.verify_token_owner(token_contract_hash, token_id, buyer)
+    }
+
+    fn close_listing(&mut self, caller: Key, token_contract_string: &str, token_id: &str) -> Result<(), ApiError> {
+        let token_contract_hash = parse_token_contract_hash(token_contract_string)?;
+        let listing_id = self.get_id(token_contract_string, token_id);
+        let custody = self.in_custody(&listing_id);
+        let seller = if custody {
+            self.get_active_listing(&listing_id)?.seller
+        } else {
+            self.get_token_owner(token_contract_hash, token_id)?
+        };
+
+        if caller != seller {
+            return Err(Error::PermissionDenied.into());
+        }
+
+        let listing = self.get_active_listing(&listing_id)?;
+        self.host.dictionary_put(LISTING_DICTIONARY, &listing_id, None::<Listing>);
+        if custody {
+            self.host.dictionary_put(CUSTODY_DICTIONARY, &listing_id, false);
+        }
+        self.untrack_position(ACCOUNT_LISTINGS_DICTIONARY, &listing.seller, &listing_id);
+        self.floor_remove(&token_contract_hash, token_id);
+        self.update_collection_stats(&token_contract_hash, |stats| {
+            stats.listing_closed();
+            Ok(())
+        })?;
+
+        self.host.emit(MarketEvent::ListingCanceled {
+            token_contract: token_contract_hash,
+            token_id: String::from(token_id)
+        });
+
+        if custody {
+            let market = self.market_key();
+            self.host.transfer_from(token_contract_hash, market, seller, token_id_to_vec(token_id)?)?;
+            self.verify_token_owner(token_contract_hash, token_id, seller)?;
+        }
+        Ok(())
+    }
+
+    fn place_offer(
+        &mut self,
+        bidder: Key,
+        token_contract_string: &str,
+        token_id: &str,
+        bidder_purse: URef
+    ) -> Result<(), ApiError> {
+        // reject malformed ids up front so the offer can't become unacceptable later
+        let token_contract_hash = parse_token_contract_hash(token_contract_string)?;
+        parse_token_id(token_id)?;
+        let offers_id = self.get_id(token_contract_string, token_id);
+        let purse_balance = self.host.balance(Purse::External(bidder_purse))
+            .ok_or(Error::InvalidPurse)?;
+
+        if purse_balance < self.config::<U512>(MIN_OFFER_AMOUNT)? {
+            return Err(Error::OfferTooLow.into());
+        }
+
+        // TODO: rebalance current offer instead of error
+        if self.get_offer(&offers_id, &bidder).is_some() {
+            return Err(Error::OfferExists.into());
+        }
+
+        let mut bidders = self.get_offer_bidders(&offers_id);
+
+        if bidders.len() >= self.config::<u32>(MAX_OFFERS_PER_TOKEN)? as usize {
+            return Err(Error::TooManyOffers.into());
+        }
+
+        bidders.push(bidder);
+        self.put_offer(&offers_id, &bidder, Some(purse_balance));
+        self.host.dictionary_put(OFFER_INDEX_DICTIONARY, &offers_id, bidders);
+        self.track_position(ACCOUNT_OFFERS_DICTIONARY, &bidder, &offers_id);
+
+        self.host.emit(MarketEvent::OfferCreated {
+            buyer: bidder,
+            token_contract: token_contract_hash,
+            token_id: String::from(token_id),
+            price: purse_balance
+        });
+
+        self.host.transfer_to_purse(Purse::External(bidder_purse), Purse::Offers, purse_balance)
+    }
+
+    fn refund_offer(&mut self, bidder: Key, token_contract_string: &str, token_id: &str) -> Result<(), ApiError> {
+        let token_contract_hash = parse_token_contract_hash(token_contract_string)?;
+        let offers_id = self.get_id(token_contract_string, token_id);
+
+        let amount = self.get_offer(&offers_id, &bidder)
+            .ok_or(Error::NoMatchingOffer)?;
+
+        let mut bidders = self.get_offer_bidders(&offers_id);
+
+        // the offer is gone before the refund goes out
+        self.put_offer(&offers_id, &bidder, None);
+        bidders.retain(|other| *other != bidder);
+        self.host.dictionary_put(OFFER_INDEX_DICTIONARY, &offers_id, bidders);
+        self.untrack_position(ACCOUNT_OFFERS_DICTIONARY, &bidder, &offers_id);
+
+        self.host.emit(MarketEvent::OfferWithdraw {
+            buyer: bidder,
+            token_contract: token_contract_hash,
+            token_id: String::from(token_id)
+        });
+
+        self.pay_out(Purse::Offers, bidder, amount)
+    }
+
+    fn settle_offer(
+        &mut self,
+        seller: Key,
+        token_contract_string: &str,
+        token_id: &str,
+        accepted_offer: &str
+    ) -> Result<(), ApiError> {
+        let token_contract_hash = parse_token_contract_hash(token_contract_string)?;
+        let token_ids = token_id_to_vec(token_id)?;
+        let accepted_bidder_hash = parse_account_key(accepted_offer)?;
+        let offers_id = self.get_id(token_contract_string, token_id);
+
+        let amount = self.get_offer(&offers_id, &accepted_bidder_hash)
+            .ok_or(Error::NoMatchingOffer)?;
+
+        let mut bidders = self.get_offer_bidders(&offers_id);
+
+        // offers & listings share ids. only the seller of a listing in custody may accept for
+        // its token, which the market then hands over itself
+        let custody = self.in_custody(&offers_id);
+        let holder = if custody {
+            if self.get_active_listing(&offers_id)?.seller != seller {
+                return Err(Error::PermissionDenied.into());
+            }
+            self.market_key()
+        } else {
+            seller
+        };
+
+        // the accepted offer is settled & the listing closed before the token contract runs, so
+        // a callback finds nothing left to withdraw or accept. only the seller is credited, they
+        // pull it with `claim` so a bad recipient can't make the acceptance revert. the other
+        // offers stay open for their bidders to withdraw, so the cost doesn't grow with them
+        self.credit_claim(&seller, amount)?;
+        self.put_offer(&offers_id, &accepted_bidder_hash, None);
+        bidders.retain(|other| *other != accepted_bidder_hash);
+        self.host.dictionary_put(OFFER_INDEX_DICTIONARY, &offers_id, bidders);
+        self.untrack_position(ACCOUNT_OFFERS_DICTIONARY, &accepted_bidder_hash, &offers_id);
+
+        let canceled_listing = self.force_cancel_listing(token_contract_string, token_id);
+        if custody {
+            self.host.dictionary_put(CUSTODY_DICTIONARY, &offers_id, false);
+        }
+        self.floor_remove(&token_contract_hash, token_id);
+        self.update_collection_stats(&token_contract_hash, |stats| {
+            if canceled_listing.is_some() {
+                stats.listing_closed();
+            }
+            stats.record_sale(amount)
+        })?;
+        self.record_sale(&offers_id, amount, seller, accepted_bidder_hash, SALE_TYPE_OFFER);
+
+        self.host.emit(MarketEvent::OfferAccepted {
+            seller,
+            buyer: accepted_bidder_hash,
+            token_contract: token_contract_hash,
+            token_id: String::from(token_id),
+            price: amount
+        });
+
+        self.host.transfer_to_purse(Purse::Offers, Purse::Claims, amount)?;
+        self.host.transfer_from(token_contract_hash, holder, accepted_bidder_hash, token_ids)?;
+        self.verify_token_owner(token_contract_hash, token_id, accepted_bidder_hash)
+    }
+
+    fn pay_claim(&mut self, claimant: Key, claim_purse: Option<URef>) -> Result<(), ApiError> {
+        let amount = self.get_claim(&claimant);
+
+        if amount.is_zero() {
+            return Err(Error::NothingToClaim.into());
+        }
+
+        let claim_id = self.get_key_id(&claimant);
+        self.host.dictionary_put(CLAIM_DICTIONARY, &claim_id, U512::zero());
+
+        match (claimant, claim_purse) {
+            (_, Some(target_purse)) => self.host.transfer_to_purse(Purse::Claims, Purse::External(target_purse), amount),
+            (Key::Account(account_hash), None) => self.host.transfer_to_account(Purse::Claims, account_hash, amount),
+            // contracts have no main purse to fall back on
+            _ => Err(Error::InvalidPurse.into())
+        }
+    }
+
+    // canceled & sold listings are stored as None::<Listing> so they fail to read as a Listing
+    pub fn get_listing(&mut self, token_contract_string: &str, token_id: &str) -> Option<Listing> {
+        let (token_contract, token_id) = canonical_token(token_contract_string, token_id).ok()?;
+        let listing_id = self.get_id(&token_contract, &token_id);
+        self.find_listing(&listing_id)
+    }
+
+    /// Whether the market holds the listed token, false once the listing closes.
+    pub fn get_listing_custody(&mut self, token_contract_string: &str, token_id: &str) -> bool {
+        match canonical_token(token_contract_string, token_id) {
+            Ok((token_contract, token_id)) => {
+                let listing_id = self.get_id(&token_contract, &token_id);
+                self.in_custody(&listing_id)
+            },
+            Err(_) => false
+        }
+    }
+
+    pub fn get_offers(&mut self, token_contract_string: &str, token_id: &str) -> BTreeMap<Key, U512> {
+        let offers_id = match canonical_token(token_contract_string, token_id) {
+            Ok((token_contract, token_id)) => self.get_id(&token_contract, &token_id),
+            Err(_) => return BTreeMap::new()
+        };
+
+        self.get_offer_bidders(&offers_id)
+            .iter()
+            .filter_map(|bidder| self.get_offer(&offers_id, bidder).map(|amount| (*bidder, amount)))
+            .collect()
+    }
+
+    pub fn get_collection_stats(&mut self, token_contract_string: &str) -> Result<CollectionStats, ApiError> {
+        let token_contract_hash = parse_token_contract_hash(token_contract_string)?;
+        Ok(self.read_collection_stats(&token_contract_hash))
+    }
+
+    pub fn get_floor_price(&mut self, token_contract_string: &str) -> Result<Option<U512>, ApiError> {
+        let token_contract_hash = parse_token_contract_hash(token_contract_string)?;
+        Ok(self.floor_peek(&token_contract_hash).map(|(price, _)| price))
+    }
+
+    pub fn get_sales_history(&mut self, token_contract_string: &str, token_id: &str) -> Vec<Sale> {
+        let sale_id = match canonical_token(token_contract_string, token_id) {
+            Ok((token_contract, token_id)) => self.get_id(&token_contract, &token_id),
+            Err(_) => return Vec::new()
+        };
+        self.read_sales_history(&sale_id)
+    }
+
+    /// Motes waiting for `claimant` in the claims purse.
+    pub fn get_claim(&mut self, claimant: &Key) -> U512 {
+        let claim_id = self.get_key_id(claimant);
+        self.host.dictionary_get(CLAIM_DICTIONARY, &claim_id).unwrap_or_default()
+    }
+
+    /// The listing or offer ids `account` has open, `dictionary_name` picks which.
+    pub fn get_positions(&mut self, dictionary_name: &str, account: &Key) -> Vec<String> {
+        let account_id = self.get_key_id(account);
+        let count: u32 = self.host.dictionary_get(dictionary_name, &account_id).unwrap_or_default();
+        (0..count)
+            .filter_map(|index| {
+                let entry_id = self.get_id(&account_id, &index.to_string());
+                self.host.dictionary_get(dictionary_name, &entry_id)
+            })
+            .collect()
+    }
+
+    // compact list of who currently has a bid on the token, capped at MAX_OFFERS_PER_TOKEN
+    pub fn get_offer_bidders(&mut self, offers_id: &str) -> Vec<Key> {
+        self.host.dictionary_get(OFFER_INDEX_DICTIONARY, offers_id).unwrap_or_default()
+    }
+
+    pub fn get_offer(&mut self, offers_id: &str, bidder: &Key) -> Option<U512> {
+        let offer_id = self.get_offer_id(offers_id, bidder);
+        self.host.dictionary_get::<Option<U512>>(OFFER_DICTIONARY, &offer_id).flatten()
+    }
+
+    fn in_custody(&mut self, listing_id: &str) -> bool {
+        self.host.dictionary_get(CUSTODY_DICTIONARY, listing_id).unwrap_or_default()
+    }
+
+    // the owner CEP-47 records for tokens in custody
+    fn market_key(&self) -> Key {
+        Key::from(self.host.market_package())
+    }
+
+    fn config<T: CLTyped + FromBytes>(&mut self, name: &str) -> Result<T, ApiError> {
+        self.host.named_value(name).ok_or(ApiError::MissingKey)
+    }
+
+    fn transfer_approved(&mut self, token_contract_hash: ContractHash, token_id: &str, owner: Key) -> Result<bool, ApiError> {
+        let approved = self.host.get_approved(token_contract_hash, owner, parse_token_id(token_id)?)
+            .ok_or(Error::NeedsTransferApproval)?
+            .into_hash()
+            .ok_or(Error::MissingApprovalResult)?;
+
+        Ok(self.host.market_package().value() == approved)
+    }
+
+    // a missing owner means the token contract doesn't know the id
+    fn get_token_owner(&mut self, token_contract_hash: ContractHash, token_id: &str) -> Result<Key, ApiError> {
+        let token_id = parse_token_id(token_id)?;
+        Ok(self.host.owner_of(token_contract_hash, token_id).ok_or(Error::InvalidTokenId)?)
+    }
+
+    // token contracts aren't trusted to have moved the token just because `transfer_from` returned
+    fn verify_token_owner(&mut self, token_contract_hash: ContractHash, token_id: &str, expected_owner: Key) -> Result<(), ApiError> {
+        if self.get_token_owner(token_contract_hash, token_id)? != expected_owner {
+            return Err(Error::TokenNotTransferred.into());
+        }
+        Ok(())
+    }
+
+    fn get_active_listing(&mut self, listing_id: &str) -> Result<Listing, ApiError> {
+        match self.host.dictionary_get::<Listing>(LISTING_DICTIONARY, listing_id) {
+            Some(listing) => Ok(listing),
+            // only a closed listing reads back as an empty Option<Listing>
+            None => match self.host.dictionary_get::<Option<Listing>>(LISTING_DICTIONARY, listing_id) {
+                Some(_) => Err(Error::ListingCanceledOrSold.into()),
+                None => Err(Error::ListingDoesNotExist.into())
+            }
+        }
+    }
+
+    fn find_listing(&mut self, listing_id: &str) -> Option<Listing> {
+        self.host.dictionary_get(LISTING_DICTIONARY, listing_id)
+    }
+
+    // use when it doesn't matter if listing exists or not & no event needed
+    fn force_cancel_listing(&mut self, token_contract: &str, token_id: &str) -> Option<Listing> {
+        let listing_id = self.get_id(token_contract, token_id);
+        let listing = self.find_listing(&listing_id);
+        if let Some(listing) = &listing {
+            self.untrack_position(ACCOUNT_LISTINGS_DICTIONARY, &listing.seller, &listing_id);
+        }
+        self.host.dictionary_put(LISTING_DICTIONARY, &listing_id, None::<Listing>);
+        listing
+    }
+
+    // a listing whose token moved or whose seller took back the market's approval can't be
+    // filled, it's closed like a cancel. tokens in custody can't go stale
+    fn close_stale_listing(&mut self, token_contract_string: &str, token_id: &str) -> Result<bool, ApiError> {
+        let token_contract_hash = parse_token_contract_hash(token_contract_string)?;
+        let listing_id = self.get_id(token_contract_string, token_id);
+        if self.in_custody(&listing_id) {
+            return Ok(false);
+        }
+
+        let listing = self.get_active_listing(&listing_id)?;
+        let owner = self.host.owner_of(token_contract_hash, parse_token_id(token_id)?);
+        if owner == Some(listing.seller)
+            && self.transfer_approved(token_contract_hash, token_id, listing.seller).unwrap_or(false) {
+            return Ok(false);
+        }
+
+        self.host.dictionary_put(LISTING_DICTIONARY, &listing_id, None::<Listing>);
+        self.untrack_position(ACCOUNT_LISTINGS_DICTIONARY, &listing.seller, &listing_id);
+        self.floor_remove(&token_contract_hash, token_id);
+        self.update_collection_stats(&token_contract_hash, |stats| {
+            stats.listing_closed();
+            Ok(())
+        })?;
+
+        self.host.emit(MarketEvent::ListingCanceled {
+            token_contract: token_contract_hash,
+            token_id: String::from(token_id)
+        });
+        Ok(true)
+    }
+
+    fn read_collection_stats(&mut self, token_contract: &ContractHash) -> CollectionStats {
+        self.host.dictionary_get(COLLECTION_STATS_DICTIONARY, &get_contract_id(token_contract))
+            .unwrap_or_default()
+    }
+
+    fn update_collection_stats<F>(&mut self, token_contract: &ContractHash, update: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut CollectionStats) -> Result<(), Error>
+    {
+        let mut stats = self.read_collection_stats(token_contract);
+        update(&mut stats)?;
+        self.host.dictionary_put(COLLECTION_STATS_DICTIONARY, &get_contract_id(token_contract), stats);
+        Ok(())
+    }
+
+    // the cheapest active listing, which may be stale until `buy_floor` or its seller closes it
+    fn floor_peek(&mut self, token_contract: &ContractHash) -> Option<(U512, String)> {
+        if self.floor_counters(token_contract).0 == 0 {
+            return None;
+        }
+        self.floor_entry(token_contract, 0).map(|(price, _, token_id)| (price, token_id))
+    }
+
+    // replaces any earlier price for the token, which covers listing updates
+    fn floor_insert(&mut self, token_contract: &ContractHash, token_id: &str, price: U512) -> () {
+        self.floor_remove(token_contract, token_id);
+        let (size, sequence) = self.floor_counters(token_contract);
+        self.put_floor_counters(token_contract, size + 1, sequence + 1);
+        self.floor_place(token_contract, size, (price, sequence, String::from(token_id)), size + 1);
+    }
+
+    // the last entry fills the freed slot & moves up or down from there
+    fn floor_remove(&mut self, token_contract: &ContractHash, token_id: &str) -> () {
+        let slot_id = self.get_id(&get_contract_id(token_contract), token_id);
+        let slot = match self.host.dictionary_get::<Option<u32>>(FLOOR_SLOT_DICTIONARY, &slot_id).flatten() {
+            Some(slot) => slot,
+            None => return
+        };
+        self.host.dictionary_put(FLOOR_SLOT_DICTIONARY, &slot_id, None::<u32>);
+
+        let (size, sequence) = self.floor_counters(token_contract);
+        let last = size - 1;
+        self.put_floor_counters(token_contract, last, sequence);
+        if slot != last {
+            if let Some(entry) = self.floor_entry(token_contract, last) {
+                self.floor_place(token_contract, slot, entry, last);
+            }
+        }
+    }
+
+    fn floor_counters(&mut self, token_contract: &ContractHash) -> (u32, u64) {
+        self.host.dictionary_get(FLOOR_COUNTERS_DICTIONARY, &get_contract_id(token_contract))
+            .unwrap_or_default()
+    }
+
+    fn put_floor_counters(&mut self, token_contract: &ContractHash, size: u32, sequence: u64) -> () {
+        self.host.dictionary_put(FLOOR_COUNTERS_DICTIONARY, &get_contract_id(token_contract), (size, sequence));
+    }
+
+    fn floor_entry(&mut self, token_contract: &ContractHash, slot: u32) -> Option<FloorEntry> {
+        let entry_id = self.get_id(&get_contract_id(token_contract), &slot.to_string());
+        self.host.dictionary_get(FLOOR_DICTIONARY, &entry_id)
+    }
+
+    fn put_floor_entry(&mut self, token_contract: &ContractHash, slot: u32, entry: FloorEntry) -> () {
+        let contract_id = get_contract_id(token_contract);
+        let slot_id = self.get_id(&contract_id, &entry.2);
+        self.host.dictionary_put(FLOOR_SLOT_DICTIONARY, &slot_id, Some(slot));
+        let entry_id = self.get_id(&contract_id, &slot.to_string());
+        self.host.dictionary_put(FLOOR_DICTIONARY, &entry_id, entry);
+    }
+
+    // sifts `entry` from `slot` to where it belongs in a heap of `size` entries
+    fn floor_place(&mut self, token_contract: &ContractHash, mut slot: u32, entry: FloorEntry, size: u32) -> () {
+        let order = |entry: &FloorEntry| (entry.0, entry.1);
+
+        while slot > 0 {
+            let parent_slot = (slot - 1) / 2;
+            match self.floor_entry(token_contract, parent_slot) {
+                Some(parent) if order(&parent) > order(&entry) => {
+                    self.put_floor_entry(token_contract, slot, parent);
+                    slot = parent_slot;
+                },
+                _ => break
+            }
+        }
+
+        loop {
+            let mut child = None;
+            for child_slot in [2 * slot + 1, 2 * slot + 2] {
+                if child_slot >= size {
+                    break;
+                }
+                if let Some(candidate) = self.floor_entry(token_contract, child_slot) {
+                    let smaller = match &child {
+                        Some((_, current)) => order(&candidate) < order(current),
+                        None => true
+                    };
+                    if smaller {
+                        child = Some((child_slot, candidate));
+                    }
+                }
+            }
+            match child {
+                Some((child_slot, candidate)) if order(&candidate) < order(&entry) => {
+                    self.put_floor_entry(token_contract, slot, candidate);
+                    slot = child_slot;
+                },
+                _ => break
+            }
+        }
+
+        self.put_floor_entry(token_contract, slot, entry);
+    }
+
+    fn track_position(&mut self, dictionary_name: &str, account: &Key, id: &str) -> () {
+        let account_id = self.get_key_id(account);
+        if self.position_index(dictionary_name, &account_id, id).is_some() {
+            return;
+        }
+        let count: u32 = self.host.dictionary_get(dictionary_name, &account_id).unwrap_or_default();
+        self.put_position(dictionary_name, &account_id, count, id);
+        self.host.dictionary_put(dictionary_name, &account_id, count + 1);
+    }
+
+    // the account's last id moves into the freed index
+    fn untrack_position(&mut self, dictionary_name: &str, account: &Key, id: &str) -> () {
+        let account_id = self.get_key_id(account);
+        let index = match self.position_index(dictionary_name, &account_id, id) {
+            Some(index) => index,
+            None => return
+        };
+        let index_id = self.get_id(&account_id, id);
+        self.host.dictionary_put(dictionary_name, &index_id, None::<u32>);
+
+        let count: u32 = self.host.dictionary_get(dictionary_name, &account_id).unwrap_or_default();
+        let last = count - 1;
+        if index != last {
+            let last_id = self.get_id(&account_id, &last.to_string());
+            if let Some(moved) = self.host.dictionary_get::<String>(dictionary_name, &last_id) {
+                self.put_position(dictionary_name, &account_id, index, &moved);
+            }
+        }
+        self.host.dictionary_put(dictionary_name, &account_id, last);
+    }
+
+    // None once the position is closed, dictionaries have no remove
+    fn position_index(&mut self, dictionary_name: &str, account_id: &str, id: &str) -> Option<u32> {
+        let index_id = self.get_id(account_id, id);
+        self.host.dictionary_get::<Option<u32>>(dictionary_name, &index_id).flatten()
+    }
+
+    fn put_position(&mut self, dictionary_name: &str, account_id: &str, index: u32, id: &str) -> () {
+        let entry_id = self.get_id(account_id, &index.to_string());
+        self.host.dictionary_put(dictionary_name, &entry_id, String::from(id));
+        let index_id = self.get_id(account_id, id);
+        self.host.dictionary_put(dictionary_name, &index_id, Some(index));
+    }
+
+    // each bid lives under its own key so a bid only touches its own entry
+    fn get_offer_id(&self, offers_id: &str, bidder: &Key) -> String {
+        self.get_id(offers_id, &self.get_key_id(bidder))
+    }
+
+    // None clears the bid, dictionaries have no remove
+    fn put_offer(&mut self, offers_id: &str, bidder: &Key, amount: Option<U512>) -> () {
+        let offer_id = self.get_offer_id(offers_id, bidder);
+        self.host.dictionary_put(OFFER_DICTIONARY, &offer_id, amount);
+    }
+
+    fn credit_claim(&mut self, claimant: &Key, amount: U512) -> Result<(), Error> {
+        let balance = self.get_claim(claimant)
+            .checked_add(amount)
+            .ok_or(Error::ArithmeticOverflow)?;
+        let claim_id = self.get_key_id(claimant);
+        self.host.dictionary_put(CLAIM_DICTIONARY, &claim_id, balance);
+        Ok(())
+    }
+
+    // accounts are paid directly, contracts can't be so their share waits in the claims purse
+    fn pay_out(&mut self, source_purse: Purse, recipient: Key, amount: U512) -> Result<(), ApiError> {
+        match recipient {
+            Key::Account(account_hash) => self.host.transfer_to_account(source_purse, account_hash, amount),
+            Key::Hash(_) => {
+                self.host.transfer_to_purse(source_purse, Purse::Claims, amount)?;
+                Ok(self.credit_claim(&recipient, amount)?)
+            },
+            _ => Err(Error::InvalidAccountKey.into())
+        }
+    }
+
+    fn read_sales_history(&mut self, sale_id: &str) -> Vec<Sale> {
+        self.host.dictionary_get(SALES_HISTORY_DICTIONARY, sale_id).unwrap_or_default()
+    }
+
+    fn record_sale(&mut self, sale_id: &str, price: U512, seller: Key, buyer: Key, sale_type: u8) -> () {
+        let mut sales = self.read_sales_history(sale_id);
+
+        if sales.len() >= MAX_SALES_HISTORY {
+            sales.remove(0);
+        }
+        sales.push(Sale {
+            price,
+            seller,
+            buyer,
+            timestamp: self.host.block_time(),
+            sale_type
+        });
+
+        self.host.dictionary_put(SALES_HISTORY_DICTIONARY, sale_id, sales);
+    }
+}
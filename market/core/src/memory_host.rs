@@ -0,0 +1,225 @@
+use alloc::{
+    string::String,
+    vec::Vec,
+    collections::BTreeMap
+};
+
+use blake2::{
+    digest::{Update, VariableOutput},
+    VarBlake2b
+};
+use casper_types::{
+    account::AccountHash,
+    bytesrepr::{FromBytes, ToBytes},
+    AccessRights, ApiError, CLTyped, CLValue, ContractHash, ContractPackageHash, Key, URef, U256, U512};
+
+use crate::{
+    error::Error,
+    event::MarketEvent,
+    host::{MarketHost, Purse},
+    market::{Market, MAX_OFFERS_PER_TOKEN, MIN_OFFER_AMOUNT}
+};
+
+/// The chain in memory: the market's storage, honest CEP-47 collections, purses & account
+/// balances. Values are kept as `CLValue`s so reading one back as another type fails like it
+/// does in the engine. Use `Market::execute` to discard an entry point's writes when it errors.
+#[derive(Debug, Clone)]
+pub struct MemoryHost {
+    dictionaries: BTreeMap<(String, String), CLValue>,
+    named_values: BTreeMap<String, CLValue>,
+    market_package: ContractPackageHash,
+    owners: BTreeMap<(ContractHash, U256), Key>,
+    approvals: BTreeMap<(ContractHash, U256), Key>,
+    purses: BTreeMap<URef, U512>,
+    offers_purse: U512,
+    claims_purse: U512,
+    accounts: BTreeMap<AccountHash, U512>,
+    next_purse: u64,
+    pub block_time: u64,
+    pub events: Vec<MarketEvent>
+}
+
+impl MemoryHost {
+    pub fn new(max_offers_per_token: u32, min_offer_amount: U512) -> Self {
+        let mut named_values = BTreeMap::new();
+        named_values.insert(String::from(MAX_OFFERS_PER_TOKEN), CLValue::from_t(max_offers_per_token).unwrap());
+        named_values.insert(String::from(MIN_OFFER_AMOUNT), CLValue::from_t(min_offer_amount).unwrap());
+
+        MemoryHost {
+            dictionaries: BTreeMap::new(),
+            named_values,
+            market_package: ContractPackageHash::new([0xaa; 32]),
+            owners: BTreeMap::new(),
+            approvals: BTreeMap::new(),
+            purses: BTreeMap::new(),
+            offers_purse: U512::zero(),
+            claims_purse: U512::zero(),
+            accounts: BTreeMap::new(),
+            next_purse: 0,
+            block_time: 0,
+            events: Vec::new()
+        }
+    }
+
+    pub fn market_package_key(&self) -> Key {
+        Key::from(self.market_package)
+    }
+
+    pub fn mint(&mut self, token_contract: ContractHash, token_id: U256, owner: Key) -> () {
+        self.owners.insert((token_contract, token_id), owner);
+    }
+
+    pub fn approve(&mut self, token_contract: ContractHash, token_id: U256, spender: Key) -> () {
+        self.approvals.insert((token_contract, token_id), spender);
+    }
+
+    pub fn owner(&self, token_contract: ContractHash, token_id: U256) -> Option<Key> {
+        self.owners.get(&(token_contract, token_id)).copied()
+    }
+
+    /// A fresh purse holding `balance`, like the one the payment wasm hands the market.
+    pub fn new_purse(&mut self, balance: U512) -> URef {
+        self.next_purse += 1;
+        let mut address = [0; 32];
+        address[..8].copy_from_slice(&self.next_purse.to_le_bytes());
+        let purse = URef::new(address, AccessRights::READ_ADD_WRITE);
+        self.purses.insert(purse, balance);
+        purse
+    }
+
+    pub fn purse_balance(&self, purse: URef) -> U512 {
+        self.purses.get(&purse).copied().unwrap_or_default()
+    }
+
+    pub fn offers_purse_balance(&self) -> U512 {
+        self.offers_purse
+    }
+
+    pub fn claims_purse_balance(&self) -> U512 {
+        self.claims_purse
+    }
+
+    pub fn account_balance(&self, account: AccountHash) -> U512 {
+        self.accounts.get(&account).copied().unwrap_or_default()
+    }
+
+    fn purse_mut(&mut self, purse: Purse) -> Option<&mut U512> {
+        match purse {
+            Purse::External(uref) => self.purses.get_mut(&uref),
+            Purse::Offers => Some(&mut self.offers_purse),
+            Purse::Claims => Some(&mut self.claims_purse)
+        }
+    }
+
+    fn debit(&mut self, source: Purse, amount: U512) -> Result<(), ApiError> {
+        let balance = self.purse_mut(source).ok_or(ApiError::InvalidPurse)?;
+        *balance = balance.checked_sub(amount).ok_or(ApiError::Transfer)?;
+        Ok(())
+    }
+}
+
+impl MarketHost for MemoryHost {
+    fn dictionary_get<T: CLTyped + FromBytes>(&mut self, dictionary: &str, key: &str) -> Option<T> {
+        self.dictionaries.get(&(String::from(dictionary), String::from(key)))
+            .and_then(|value| value.clone().into_t().ok())
+    }
+
+    fn dictionary_put<T: CLTyped + ToBytes>(&mut self, dictionary: &str, key: &str, value: T) -> () {
+        let value = CLValue::from_t(value).expect("market values should serialize");
+        self.dictionaries.insert((String::from(dictionary), String::from(key)), value);
+    }
+
+    fn named_value<T: CLTyped + FromBytes>(&mut self, name: &str) -> Option<T> {
+        self.named_values.get(name).and_then(|value| value.clone().into_t().ok())
+    }
+
+    fn put_named_value<T: CLTyped + ToBytes>(&mut self, name: &str, value: T) -> () {
+        let value = CLValue::from_t(value).expect("market values should serialize");
+        self.named_values.insert(String::from(name), value);
+    }
+
+    fn blake2b(&self, bytes: &[u8]) -> [u8; 32] {
+        let mut result = [0; 32];
+        let mut hasher = VarBlake2b::new(32).unwrap();
+        hasher.update(bytes);
+        hasher.finalize_variable(|hash| result.copy_from_slice(hash));
+        result
+    }
+
+    fn block_time(&self) -> u64 {
+        self.block_time
+    }
+
+    fn emit(&mut self, event: MarketEvent) -> () {
+        self.events.push(event);
+    }
+
+    fn market_package(&self) -> ContractPackageHash {
+        self.market_package
+    }
+
+    fn owner_of(&mut self, token_contract: ContractHash, token_id: U256) -> Option<Key> {
+        self.owner(token_contract, token_id)
+    }
+
+    fn get_approved(&mut self, token_contract: ContractHash, _owner: Key, token_id: U256) -> Option<Key> {
+        self.approvals.get(&(token_contract, token_id)).copied()
+    }
+
+    // CEP-47 only lets the market move its own tokens & those their owner approved it for
+    fn transfer_from(
+        &mut self,
+        token_contract: ContractHash,
+        sender: Key,
+        recipient: Key,
+        token_ids: Vec<U256>
+    ) -> Result<(), ApiError> {
+        let market = self.market_package_key();
+        for token_id in token_ids {
+            let token = (token_contract, token_id);
+            let allowed = sender == market || self.approvals.get(&token) == Some(&market);
+            if self.owners.get(&token) != Some(&sender) || !allowed {
+                return Err(ApiError::PermissionDenied);
+            }
+            self.owners.insert(token, recipient);
+            self.approvals.remove(&token);
+        }
+        Ok(())
+    }
+
+    fn balance(&mut self, purse: Purse) -> Option<U512> {
+        self.purse_mut(purse).map(|balance| *balance)
+    }
+
+    fn transfer_to_purse(&mut self, source: Purse, target: Purse, amount: U512) -> Result<(), ApiError> {
+        if self.purse_mut(target).is_none() {
+            return Err(ApiError::InvalidPurse);
+        }
+        self.debit(source, amount)?;
+        let balance = self.purse_mut(target).ok_or(ApiError::InvalidPurse)?;
+        *balance = balance.checked_add(amount).ok_or(Error::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    fn transfer_to_account(&mut self, source: Purse, target: AccountHash, amount: U512) -> Result<(), ApiError> {
+        self.debit(source, amount)?;
+        let balance = self.accounts.entry(target).or_default();
+        *balance = balance.checked_add(amount).ok_or(Error::ArithmeticOverflow)?;
+        Ok(())
+    }
+}
+
+impl Market<MemoryHost> {
+    /// Runs an entry point like a deploy: if it errors, everything it wrote is rolled back.
+    pub fn execute<T, F>(&mut self, entry_point: F) -> Result<T, ApiError>
+    where
+        F: FnOnce(&mut Self) -> Result<T, ApiError>
+    {
+        let snapshot = self.host().clone();
+        let result = entry_point(self);
+        if result.is_err() {
+            *self.host_mut() = snapshot;
+        }
+        result
+    }
+}
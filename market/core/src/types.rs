@@ -0,0 +1,57 @@
+use alloc::string::String;
+
+use casper_types::{ContractHash, Key, U512};
+use casper_types_derive::{CLTyped, FromBytes, ToBytes};
+
+use crate::error::Error;
+
+pub const SALE_TYPE_LISTING: u8 = 0;
+pub const SALE_TYPE_OFFER: u8 = 1;
+
+// struct being used only for workaround to dictionary limitation (no remove function)
+#[derive(CLTyped, ToBytes, FromBytes, Debug, Clone, PartialEq, Eq)]
+pub struct Listing {
+    pub seller: Key,
+    pub token_contract: ContractHash,
+    pub token_id: String,
+    pub price: U512
+}
+
+#[derive(CLTyped, ToBytes, FromBytes, Debug, Clone, PartialEq, Eq)]
+pub struct Sale {
+    pub price: U512,
+    pub seller: Key,
+    pub buyer: Key,
+    pub timestamp: u64,
+    pub sale_type: u8
+}
+
+// running aggregates per token contract, updated on every listing change & sale
+#[derive(CLTyped, ToBytes, FromBytes, Default, Debug, Clone, PartialEq, Eq)]
+pub struct CollectionStats {
+    pub volume: U512,
+    pub sales: u64,
+    pub last_sale_price: U512,
+    pub active_listings: u64
+}
+
+impl CollectionStats {
+    pub fn listing_opened(&mut self) -> Result<(), Error> {
+        self.active_listings = self.active_listings.checked_add(1)
+            .ok_or(Error::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    pub fn listing_closed(&mut self) -> () {
+        self.active_listings = self.active_listings.saturating_sub(1);
+    }
+
+    pub fn record_sale(&mut self, price: U512) -> Result<(), Error> {
+        self.volume = self.volume.checked_add(price)
+            .ok_or(Error::ArithmeticOverflow)?;
+        self.sales = self.sales.checked_add(1)
+            .ok_or(Error::ArithmeticOverflow)?;
+        self.last_sale_price = price;
+        Ok(())
+    }
+}
@@ -0,0 +1,276 @@
+//! Random sequences of market calls against `MemoryHost`, checking after every step that escrow
+//! & listings still add up. A failing case shrinks to the shortest sequence that breaks one.
+
+use std::collections::BTreeMap;
+
+use casper_types::{account::AccountHash, ContractHash, Key, URef, U256, U512};
+use market_core::{Market, MemoryHost};
+use proptest::{collection::vec, prelude::*, test_runner::TestCaseError};
+
+const MAX_OFFERS_PER_TOKEN: u32 = 3;
+const MIN_OFFER_AMOUNT: u64 = 100;
+
+// more actors than MAX_OFFERS_PER_TOKEN so sequences can hit TooManyOffers
+const ACTORS: usize = 4;
+const TOKENS: usize = 3;
+
+#[derive(Debug, Clone)]
+enum Op {
+    List { caller: usize, token: usize, price: u64, approve: bool, custody: bool },
+    Cancel { caller: usize, token: usize },
+    Buy { buyer: usize, token: usize, amount: u64 },
+    Offer { bidder: usize, token: usize, amount: u64 },
+    Withdraw { bidder: usize, token: usize },
+    Accept { caller: usize, token: usize, bidder: usize },
+    Claim { caller: usize }
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let actors = || 0..ACTORS;
+    let tokens = || 0..TOKENS;
+    prop_oneof![
+        (actors(), tokens(), 1..500u64, any::<bool>(), any::<bool>())
+            .prop_map(|(caller, token, price, approve, custody)| Op::List { caller, token, price, approve, custody }),
+        (actors(), tokens()).prop_map(|(caller, token)| Op::Cancel { caller, token }),
+        (actors(), tokens(), 0..600u64).prop_map(|(buyer, token, amount)| Op::Buy { buyer, token, amount }),
+        (actors(), tokens(), 0..500u64).prop_map(|(bidder, token, amount)| Op::Offer { bidder, token, amount }),
+        (actors(), tokens()).prop_map(|(bidder, token)| Op::Withdraw { bidder, token }),
+        (actors(), tokens(), actors()).prop_map(|(caller, token, bidder)| Op::Accept { caller, token, bidder }),
+        actors().prop_map(|caller| Op::Claim { caller })
+    ]
+}
+
+fn actor(index: usize) -> Key {
+    Key::Account(AccountHash::new([index as u8 + 1; 32]))
+}
+
+fn account_hash(key: Key) -> AccountHash {
+    key.into_account().unwrap()
+}
+
+fn token_contract() -> ContractHash {
+    ContractHash::new([0xbb; 32])
+}
+
+fn token_arg() -> String {
+    token_contract().to_formatted_string()
+}
+
+fn token_id(token: usize) -> U256 {
+    U256::from(token)
+}
+
+/// The market plus what the test expects of it: which offers are open & what each actor should
+/// have been paid so far, whether it landed in their account or is waiting as a claim.
+struct Sim {
+    market: Market<MemoryHost>,
+    purses: Vec<URef>,
+    minted: U512,
+    offers: BTreeMap<(usize, Key), U512>,
+    received: BTreeMap<Key, U512>
+}
+
+impl Sim {
+    fn new(owners: &[usize]) -> Self {
+        let mut market = Market::new(MemoryHost::new(MAX_OFFERS_PER_TOKEN, U512::from(MIN_OFFER_AMOUNT)));
+        for (token, owner) in owners.iter().enumerate() {
+            market.host_mut().mint(token_contract(), token_id(token), actor(*owner));
+        }
+
+        Sim {
+            market,
+            purses: Vec::new(),
+            minted: U512::zero(),
+            offers: BTreeMap::new(),
+            received: BTreeMap::new()
+        }
+    }
+
+    fn new_purse(&mut self, amount: u64) -> URef {
+        let purse = self.market.host_mut().new_purse(U512::from(amount));
+        self.purses.push(purse);
+        self.minted += U512::from(amount);
+        purse
+    }
+
+    fn pay(&mut self, recipient: Key, amount: U512) -> () {
+        *self.received.entry(recipient).or_default() += amount;
+    }
+
+    fn owner(&self, token: usize) -> Option<Key> {
+        self.market.host().owner(token_contract(), token_id(token))
+    }
+
+    fn apply(&mut self, op: &Op) -> Result<(), TestCaseError> {
+        match *op {
+            Op::List { caller, token, price, approve, custody } => {
+                let caller = actor(caller);
+                if approve && self.owner(token) == Some(caller) {
+                    let host = self.market.host_mut();
+                    let package = host.market_package_key();
+                    host.approve(token_contract(), token_id(token), package);
+                }
+                let _ = self.market.execute(|market| if custody {
+                    market.create_custody_listing(caller, &token_arg(), &token.to_string(), U512::from(price))
+                } else {
+                    market.create_listing(caller, &token_arg(), &token.to_string(), U512::from(price))
+                });
+            },
+            Op::Cancel { caller, token } => {
+                let _ = self.market.execute(|market| market.cancel_listing(actor(caller), &token_arg(), &token.to_string()));
+            },
+            Op::Buy { buyer, token, amount } => {
+                let buyer = actor(buyer);
+                let purse = self.new_purse(amount);
+                let listing = self.market.get_listing(&token_arg(), &token.to_string());
+                let result = self.market.execute(|market| market.buy_listing(buyer, &token_arg(), &token.to_string(), purse));
+
+                if result.is_ok() {
+                    let listing = listing.expect("only a listed token can be bought");
+                    self.pay(listing.seller, listing.price);
+                    prop_assert_eq!(self.market.host().purse_balance(purse), U512::from(amount) - listing.price);
+                    prop_assert_eq!(self.owner(token), Some(buyer));
+                    prop_assert_eq!(self.market.get_listing(&token_arg(), &token.to_string()), None);
+                }
+            },
+            Op::Offer { bidder, token, amount } => {
+                let bidder = actor(bidder);
+                let purse = self.new_purse(amount);
+                let result = self.market.execute(|market| market.make_offer(bidder, &token_arg(), &token.to_string(), purse));
+
+                if result.is_ok() {
+                    let previous = self.offers.insert((token, bidder), U512::from(amount));
+                    prop_assert_eq!(previous, None, "a second offer on the same token was accepted");
+                    prop_assert_eq!(self.market.host().purse_balance(purse), U512::zero());
+                }
+            },
+            Op::Withdraw { bidder, token } => {
+                let bidder = actor(bidder);
+                let result = self.market.execute(|market| market.withdraw_offer(bidder, &token_arg(), &token.to_string()));
+
+                if result.is_ok() {
+                    let amount = self.offers.remove(&(token, bidder));
+                    prop_assert!(amount.is_some(), "withdrew an offer that wasn't open");
+                    self.pay(bidder, amount.unwrap());
+                }
+            },
+            Op::Accept { caller, token, bidder } => {
+                let (seller, accepted) = (actor(caller), actor(bidder));
+                let result = self.market.execute(|market| {
+                    market.accept_offer(seller, &token_arg(), &token.to_string(), &accepted.to_formatted_string())
+                });
+
+                if result.is_ok() {
+                    // only the accepted offer settles, the others stay open for their bidders
+                    let amount = self.offers.remove(&(token, accepted));
+                    prop_assert!(amount.is_some(), "accepted an offer that wasn't open");
+                    self.pay(seller, amount.unwrap());
+                    prop_assert_eq!(self.owner(token), Some(accepted));
+                    prop_assert_eq!(self.market.get_listing(&token_arg(), &token.to_string()), None);
+                }
+            },
+            Op::Claim { caller } => {
+                let _ = self.market.execute(|market| market.claim(actor(caller), None));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_invariants(&mut self) -> Result<(), TestCaseError> {
+        // the offers purse holds exactly the open offers, per token & in total
+        let mut escrowed = U512::zero();
+        for token in 0..TOKENS {
+            let expected: BTreeMap<Key, U512> = self.offers.iter()
+                .filter(|((offer_token, _), _)| *offer_token == token)
+                .map(|((_, bidder), amount)| (*bidder, *amount))
+                .collect();
+            prop_assert_eq!(self.market.get_offers(&token_arg(), &token.to_string()), expected);
+        }
+        for amount in self.offers.values() {
+            escrowed += *amount;
+        }
+        prop_assert_eq!(self.market.host().offers_purse_balance(), escrowed);
+
+        // the claims purse holds exactly the unclaimed balances, & everyone was paid exactly
+        // what they're owed, no refund missed or paid twice
+        let mut claimable = U512::zero();
+        for index in 0..ACTORS {
+            let account = actor(index);
+            let claim = self.market.get_claim(&account);
+            claimable += claim;
+            let paid = self.market.host().account_balance(account_hash(account)) + claim;
+            prop_assert_eq!(paid, self.received.get(&account).copied().unwrap_or_default());
+        }
+        prop_assert_eq!(self.market.host().claims_purse_balance(), claimable);
+
+        // no motes are created or lost
+        let host = self.market.host();
+        let mut total = host.offers_purse_balance() + host.claims_purse_balance();
+        for purse in &self.purses {
+            total += host.purse_balance(*purse);
+        }
+        for index in 0..ACTORS {
+            total += host.account_balance(account_hash(actor(index)));
+        }
+        prop_assert_eq!(total, self.minted);
+
+        // a listing only stays open while its seller still holds the token, or the market holds
+        // it for them, so nothing sold is listed & the market holds only tokens it has listed
+        let market_key = self.market.host().market_package_key();
+        let mut listed = Vec::new();
+        for token in 0..TOKENS {
+            let custody = self.market.get_listing_custody(&token_arg(), &token.to_string());
+            prop_assert_eq!(self.owner(token) == Some(market_key), custody, "token {} custody is off", token);
+            if let Some(listing) = self.market.get_listing(&token_arg(), &token.to_string()) {
+                if !custody {
+                    prop_assert_eq!(Some(listing.seller), self.owner(token), "token {} is listed by a previous owner", token);
+                }
+                listed.push(listing.price);
+            }
+        }
+        let stats = self.market.get_collection_stats(&token_arg()).unwrap();
+        prop_assert_eq!(stats.active_listings, listed.len() as u64);
+        prop_assert_eq!(self.market.get_floor_price(&token_arg()).unwrap(), listed.iter().min().copied());
+        Ok(())
+    }
+
+    // every open offer is withdrawn & every claim collected, after which the market holds nothing
+    fn settle(&mut self) -> Result<(), TestCaseError> {
+        let open: Vec<(usize, Key)> = self.offers.keys().copied().collect();
+        for (token, bidder) in open {
+            let result = self.market.execute(|market| market.withdraw_offer(bidder, &token_arg(), &token.to_string()));
+            prop_assert!(result.is_ok(), "open offer couldn't be withdrawn: {:?}", result);
+            let amount = self.offers.remove(&(token, bidder)).unwrap();
+            self.pay(bidder, amount);
+        }
+        for index in 0..ACTORS {
+            if !self.market.get_claim(&actor(index)).is_zero() {
+                let result = self.market.execute(|market| market.claim(actor(index), None));
+                prop_assert!(result.is_ok(), "claim failed: {:?}", result);
+            }
+        }
+        self.check_invariants()?;
+
+        let host = self.market.host();
+        prop_assert_eq!(host.offers_purse_balance(), U512::zero());
+        prop_assert_eq!(host.claims_purse_balance(), U512::zero());
+        Ok(())
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn escrow_is_conserved_over_random_sequences(
+        owners in vec(0..ACTORS, TOKENS),
+        ops in vec(op(), 1..60)
+    ) {
+        let mut sim = Sim::new(&owners);
+        for op in &ops {
+            sim.apply(op)?;
+            sim.check_invariants()?;
+        }
+        sim.settle()?;
+    }
+}
@@ -0,0 +1,515 @@
+use std::collections::BTreeSet;
+
+use casper_types::{
+    account::AccountHash,
+    bytesrepr::{self, ToBytes},
+    ApiError, ContractHash, Key, U256, U512};
+use market_core::{
+    parse_account_key, parse_token_contract_hash, parse_token_id, schemas, Error, Market, MarketEvent,
+    MarketHost, MemoryHost, ACCOUNT_LISTINGS_DICTIONARY, ACCOUNT_OFFERS_DICTIONARY, EVENT_PREFIX, REENTRANCY_LOCK};
+
+const MAX_OFFERS_PER_TOKEN: u32 = 3;
+const MIN_OFFER_AMOUNT: u64 = 100;
+
+fn market() -> Market<MemoryHost> {
+    Market::new(MemoryHost::new(MAX_OFFERS_PER_TOKEN, U512::from(MIN_OFFER_AMOUNT)))
+}
+
+fn account(byte: u8) -> Key {
+    Key::Account(AccountHash::new([byte; 32]))
+}
+
+fn account_hash(key: Key) -> AccountHash {
+    key.into_account().unwrap()
+}
+
+fn token_contract() -> ContractHash {
+    ContractHash::new([0xbb; 32])
+}
+
+fn token_arg() -> String {
+    token_contract().to_formatted_string()
+}
+
+fn error(error: Error) -> Result<(), ApiError> {
+    Err(error.into())
+}
+
+fn mint(market: &mut Market<MemoryHost>, owner: Key, token_id: u64) {
+    let host = market.host_mut();
+    host.mint(token_contract(), U256::from(token_id), owner);
+    let package = host.market_package_key();
+    host.approve(token_contract(), U256::from(token_id), package);
+}
+
+fn listed(market: &mut Market<MemoryHost>, seller: Key, token_id: u64, price: u64) {
+    mint(market, seller, token_id);
+    market.create_listing(seller, &token_arg(), &token_id.to_string(), U512::from(price))
+        .expect("listing should succeed");
+}
+
+fn offer(market: &mut Market<MemoryHost>, bidder: Key, token_id: &str, amount: u64) -> Result<(), ApiError> {
+    let purse = market.host_mut().new_purse(U512::from(amount));
+    market.execute(|market| market.make_offer(bidder, &token_arg(), token_id, purse))
+}
+
+#[test]
+fn should_list_and_buy_a_token() {
+    let mut market = market();
+    let (seller, buyer) = (account(1), account(2));
+    listed(&mut market, seller, 1, 100);
+
+    let purse = market.host_mut().new_purse(U512::from(150));
+    market.execute(|market| market.buy_listing(buyer, &token_arg(), "1", purse))
+        .expect("buy should succeed");
+
+    let host = market.host();
+    assert_eq!(host.owner(token_contract(), U256::one()), Some(buyer));
+    assert_eq!(host.account_balance(account_hash(seller)), U512::from(100));
+    assert_eq!(host.purse_balance(purse), U512::from(50));
+    assert!(matches!(host.events.last(), Some(MarketEvent::ListingPurchased { .. })));
+    assert_eq!(market.get_listing(&token_arg(), "1"), None);
+    assert!(market.get_positions(ACCOUNT_LISTINGS_DICTIONARY, &seller).is_empty());
+
+    let stats = market.get_collection_stats(&token_arg()).unwrap();
+    assert_eq!((stats.sales, stats.volume, stats.active_listings), (1, U512::from(100), 0));
+    assert_eq!(market.get_sales_history(&token_arg(), "1").len(), 1);
+}
+
+#[test]
+fn should_roll_back_an_entry_point_that_errors() {
+    let mut market = market();
+    let (seller, buyer) = (account(1), account(2));
+    listed(&mut market, seller, 1, 100);
+    let events = market.host().events.len();
+
+    let purse = market.host_mut().new_purse(U512::from(99));
+    let result = market.execute(|market| market.buy_listing(buyer, &token_arg(), "1", purse));
+
+    assert_eq!(result, error(Error::BalanceInsufficient));
+    assert_eq!(market.host().purse_balance(purse), U512::from(99));
+    assert_eq!(market.host().events.len(), events);
+    assert!(market.get_listing(&token_arg(), "1").is_some());
+}
+
+#[test]
+fn should_reject_entry_points_while_the_lock_is_held() {
+    let mut market = market();
+    let (seller, buyer) = (account(1), account(2));
+    listed(&mut market, seller, 1, 100);
+    let purse = market.host_mut().new_purse(U512::from(100));
+
+    // what a token contract calling back in mid entry point runs into
+    market.host_mut().put_named_value(REENTRANCY_LOCK, true);
+    assert_eq!(market.buy_listing(buyer, &token_arg(), "1", purse), error(Error::ReentrantCall));
+    assert_eq!(market.cancel_listing(seller, &token_arg(), "1"), error(Error::ReentrantCall));
+    assert_eq!(market.claim(seller, None), error(Error::ReentrantCall));
+    assert!(market.get_listing(&token_arg(), "1").is_some());
+
+    // a failed call releases it too, even without `execute` rolling it back
+    market.host_mut().put_named_value(REENTRANCY_LOCK, false);
+    assert_eq!(market.claim(seller, None), error(Error::NothingToClaim));
+    market.buy_listing(buyer, &token_arg(), "1", purse).expect("buy should succeed");
+}
+
+#[test]
+fn should_tell_closed_listings_from_missing_ones() {
+    let mut market = market();
+    let (seller, buyer) = (account(1), account(2));
+    listed(&mut market, seller, 1, 100);
+    market.cancel_listing(seller, &token_arg(), "1").expect("cancel should succeed");
+    mint(&mut market, seller, 2);
+
+    let purse = market.host_mut().new_purse(U512::from(100));
+    assert_eq!(market.execute(|market| market.buy_listing(buyer, &token_arg(), "1", purse)), error(Error::ListingCanceledOrSold));
+    assert_eq!(market.execute(|market| market.buy_listing(buyer, &token_arg(), "2", purse)), error(Error::ListingDoesNotExist));
+}
+
+#[test]
+fn should_only_list_owned_and_approved_tokens() {
+    let mut market = market();
+    let (seller, other) = (account(1), account(2));
+    market.host_mut().mint(token_contract(), U256::one(), seller);
+
+    let list = |market: &mut Market<MemoryHost>, caller: Key| {
+        market.execute(|market| market.create_listing(caller, &token_arg(), "1", U512::from(100)))
+    };
+    assert_eq!(list(&mut market, other), error(Error::PermissionDenied));
+    assert_eq!(list(&mut market, seller), error(Error::NeedsTransferApproval));
+
+    market.host_mut().approve(token_contract(), U256::one(), other);
+    assert_eq!(list(&mut market, seller), error(Error::MissingApprovalResult));
+
+    let not_the_market = Key::Hash([9; 32]);
+    market.host_mut().approve(token_contract(), U256::one(), not_the_market);
+    assert_eq!(list(&mut market, seller), error(Error::NeedsTransferApproval));
+}
+
+#[test]
+fn should_escrow_an_offer_until_it_is_withdrawn() {
+    let mut market = market();
+    let bidder = account(3);
+
+    assert_eq!(offer(&mut market, bidder, "1", MIN_OFFER_AMOUNT - 1), error(Error::OfferTooLow));
+    offer(&mut market, bidder, "1", 250).expect("offer should succeed");
+    assert_eq!(offer(&mut market, bidder, "1", 250), error(Error::OfferExists));
+    assert_eq!(market.host().offers_purse_balance(), U512::from(250));
+    assert_eq!(market.get_offers(&token_arg(), "1").get(&bidder), Some(&U512::from(250)));
+
+    market.withdraw_offer(bidder, &token_arg(), "1").expect("withdraw should succeed");
+    assert_eq!(market.host().offers_purse_balance(), U512::zero());
+    assert_eq!(market.host().account_balance(account_hash(bidder)), U512::from(250));
+    assert!(market.get_positions(ACCOUNT_OFFERS_DICTIONARY, &bidder).is_empty());
+    assert_eq!(market.execute(|market| market.withdraw_offer(bidder, &token_arg(), "1")), error(Error::NoMatchingOffer));
+}
+
+#[test]
+fn should_leave_the_other_offers_open_when_one_is_accepted() {
+    let mut market = market();
+    let seller = account(1);
+    let bidders = [account(3), account(4), account(5)];
+    listed(&mut market, seller, 1, 1_000);
+
+    for (bidder, amount) in bidders.iter().zip([100, 200, 300]) {
+        offer(&mut market, *bidder, "1", amount).expect("offer should succeed");
+    }
+    assert_eq!(offer(&mut market, account(6), "1", 400), error(Error::TooManyOffers));
+
+    let accepted = bidders[1].to_formatted_string();
+    market.accept_offer(seller, &token_arg(), "1", &accepted).expect("accept should succeed");
+
+    assert_eq!(market.host().owner(token_contract(), U256::one()), Some(bidders[1]));
+    assert_eq!(market.host().offers_purse_balance(), U512::from(400));
+    assert_eq!(market.host().claims_purse_balance(), U512::from(200));
+    assert_eq!(market.get_claim(&seller), U512::from(200));
+    assert_eq!(market.get_claim(&bidders[0]), U512::zero());
+    assert_eq!(market.get_claim(&bidders[2]), U512::zero());
+    let open: Vec<(Key, U512)> = market.get_offers(&token_arg(), "1").into_iter().collect();
+    assert_eq!(open, vec![(bidders[0], U512::from(100)), (bidders[2], U512::from(300))]);
+    assert!(market.get_positions(ACCOUNT_OFFERS_DICTIONARY, &bidders[1]).is_empty());
+    // the open listing went with the token
+    assert_eq!(market.get_listing(&token_arg(), "1"), None);
+    assert_eq!(market.get_collection_stats(&token_arg()).unwrap().active_listings, 0);
+
+    market.claim(seller, None).expect("claim should succeed");
+    assert_eq!(market.host().account_balance(account_hash(seller)), U512::from(200));
+    assert_eq!(market.execute(|market| market.claim(seller, None)), error(Error::NothingToClaim));
+
+    // the other bidders take their offers back themselves
+    market.withdraw_offer(bidders[0], &token_arg(), "1").expect("withdraw should succeed");
+    assert_eq!(market.host().account_balance(account_hash(bidders[0])), U512::from(100));
+    assert_eq!(market.host().offers_purse_balance(), U512::from(300));
+}
+
+#[test]
+fn should_hold_a_contract_sellers_proceeds_for_claim() {
+    let mut market = market();
+    let (seller, buyer) = (Key::Hash([7; 32]), account(2));
+    listed(&mut market, seller, 1, 100);
+
+    let purse = market.host_mut().new_purse(U512::from(100));
+    market.buy_listing(buyer, &token_arg(), "1", purse).expect("buy should succeed");
+    assert_eq!(market.get_claim(&seller), U512::from(100));
+
+    assert_eq!(market.execute(|market| market.claim(seller, None)), error(Error::InvalidPurse));
+    let target = market.host_mut().new_purse(U512::zero());
+    market.claim(seller, Some(target)).expect("claim should succeed");
+    assert_eq!(market.host().purse_balance(target), U512::from(100));
+    assert_eq!(market.host().claims_purse_balance(), U512::zero());
+}
+
+fn custody_listed(market: &mut Market<MemoryHost>, seller: Key, token_id: u64, price: u64) {
+    mint(market, seller, token_id);
+    market.create_custody_listing(seller, &token_arg(), &token_id.to_string(), U512::from(price))
+        .expect("custody listing should succeed");
+}
+
+#[test]
+fn should_hold_a_custody_listed_token_until_it_sells() {
+    let mut market = market();
+    let (seller, buyer) = (account(1), account(2));
+    custody_listed(&mut market, seller, 1, 100);
+
+    let package = market.host().market_package_key();
+    assert_eq!(market.host().owner(token_contract(), U256::one()), Some(package));
+    assert!(market.get_listing_custody(&token_arg(), "1"));
+    // the market owns it now, so the seller can't list it again
+    assert_eq!(
+        market.execute(|market| market.create_listing(seller, &token_arg(), "1", U512::from(50))),
+        error(Error::PermissionDenied)
+    );
+
+    let purse = market.host_mut().new_purse(U512::from(100));
+    market.buy_listing(buyer, &token_arg(), "1", purse).expect("buy should succeed");
+    assert_eq!(market.host().owner(token_contract(), U256::one()), Some(buyer));
+    assert_eq!(market.host().account_balance(account_hash(seller)), U512::from(100));
+    assert!(!market.get_listing_custody(&token_arg(), "1"));
+    assert_eq!(market.get_sales_history(&token_arg(), "1")[0].seller, seller);
+}
+
+#[test]
+fn should_hand_a_custody_token_back_on_cancel() {
+    let mut market = market();
+    let (seller, other) = (account(1), account(2));
+    custody_listed(&mut market, seller, 1, 100);
+
+    assert_eq!(
+        market.execute(|market| market.cancel_listing(other, &token_arg(), "1")),
+        error(Error::PermissionDenied)
+    );
+    market.cancel_listing(seller, &token_arg(), "1").expect("cancel should succeed");
+    assert_eq!(market.host().owner(token_contract(), U256::one()), Some(seller));
+    assert_eq!(market.get_listing(&token_arg(), "1"), None);
+    assert!(!market.get_listing_custody(&token_arg(), "1"));
+
+    // back to an ordinary token, listable without custody
+    let package = market.host().market_package_key();
+    market.host_mut().approve(token_contract(), U256::one(), package);
+    market.create_listing(seller, &token_arg(), "1", U512::from(100)).expect("listing should succeed");
+}
+
+#[test]
+fn should_only_let_the_custody_seller_accept_an_offer() {
+    let mut market = market();
+    let (seller, bidder) = (account(1), account(3));
+    custody_listed(&mut market, seller, 1, 1_000);
+    offer(&mut market, bidder, "1", 200).expect("offer should succeed");
+
+    let accepted = bidder.to_formatted_string();
+    assert_eq!(
+        market.execute(|market| market.accept_offer(account(2), &token_arg(), "1", &accepted)),
+        error(Error::PermissionDenied)
+    );
+    market.accept_offer(seller, &token_arg(), "1", &accepted).expect("accept should succeed");
+
+    assert_eq!(market.host().owner(token_contract(), U256::one()), Some(bidder));
+    assert_eq!(market.get_claim(&seller), U512::from(200));
+    assert_eq!(market.get_listing(&token_arg(), "1"), None);
+    assert!(!market.get_listing_custody(&token_arg(), "1"));
+    assert_eq!(market.get_collection_stats(&token_arg()).unwrap().active_listings, 0);
+}
+
+#[test]
+fn should_buy_the_cheapest_listing_off_the_floor() {
+    let mut market = market();
+    let (seller, buyer) = (account(1), account(2));
+    for (token_id, price) in [(1, 300), (2, 100), (3, 200)] {
+        listed(&mut market, seller, token_id, price);
+    }
+    assert_eq!(market.get_floor_price(&token_arg()), Ok(Some(U512::from(100))));
+
+    let purse = market.host_mut().new_purse(U512::from(1_000));
+    let buy_floor = |market: &mut Market<MemoryHost>, max_price: u64| {
+        market.execute(|market| market.buy_floor(buyer, &token_arg(), U512::from(max_price), purse))
+    };
+    assert_eq!(buy_floor(&mut market, 99), error(Error::FloorAboveMaxPrice));
+    buy_floor(&mut market, 100).expect("buy_floor should succeed");
+
+    assert_eq!(market.host().owner(token_contract(), U256::from(2)), Some(buyer));
+    assert_eq!(market.get_floor_price(&token_arg()), Ok(Some(U512::from(200))));
+}
+
+#[test]
+fn should_keep_the_floor_as_listings_open_change_and_close() {
+    let mut market = market();
+    let seller = account(1);
+    let prices = [500, 300, 700, 300, 100, 900, 200, 800, 600, 400];
+    for (token_id, price) in (1..).zip(prices) {
+        listed(&mut market, seller, token_id, price);
+    }
+    assert_eq!(market.get_floor_price(&token_arg()), Ok(Some(U512::from(100))));
+
+    // a cheaper price for an existing listing moves it up, a dearer one down
+    market.create_listing(seller, &token_arg(), "6", U512::from(50)).expect("update should succeed");
+    assert_eq!(market.get_floor_price(&token_arg()), Ok(Some(U512::from(50))));
+    market.create_listing(seller, &token_arg(), "6", U512::from(950)).expect("update should succeed");
+
+    let mut expected: Vec<u64> = prices.to_vec();
+    expected[5] = 950;
+    for token_id in [5, 7, 1, 3] {
+        market.cancel_listing(seller, &token_arg(), &token_id.to_string()).expect("cancel should succeed");
+        expected[token_id - 1] = u64::MAX;
+        let floor = expected.iter().copied().filter(|price| *price != u64::MAX).min().map(U512::from);
+        assert_eq!(market.get_floor_price(&token_arg()), Ok(floor));
+    }
+}
+
+#[test]
+fn should_treat_differently_written_args_as_the_same_token() {
+    let mut market = market();
+    let (seller, buyer) = (account(1), account(2));
+    let upper_case = format!("contract-{}", hex::encode_upper(token_contract().value()));
+    mint(&mut market, seller, 1);
+
+    market.create_listing(seller, &upper_case, "1", U512::from(100)).expect("listing should succeed");
+    // relisting with other casing & a padded id updates the same listing
+    market.create_listing(seller, &token_arg(), "01", U512::from(90)).expect("update should succeed");
+    assert_eq!(market.get_collection_stats(&token_arg()).unwrap().active_listings, 1);
+    assert_eq!(market.get_listing(&upper_case, "1").map(|listing| listing.price), Some(U512::from(90)));
+
+    let purse = market.host_mut().new_purse(U512::from(100));
+    market.execute(|market| market.buy_floor(buyer, &token_arg(), U512::from(100), purse))
+        .expect("buy_floor should succeed");
+
+    assert_eq!(market.host().owner(token_contract(), U256::one()), Some(buyer));
+    assert_eq!(market.get_listing(&token_arg(), "1"), None);
+    assert_eq!(market.get_collection_stats(&upper_case).unwrap().active_listings, 0);
+    assert_eq!(market.get_sales_history(&upper_case, "001").len(), 1);
+}
+
+#[test]
+fn should_skip_floor_listings_that_cant_be_filled() {
+    let mut market = market();
+    let (seller, buyer, other) = (account(1), account(2), account(3));
+    for (token_id, price) in [(1, 100), (2, 200), (3, 300)] {
+        listed(&mut market, seller, token_id, price);
+    }
+    // token 1 is moved away & token 2's approval goes to someone else
+    market.host_mut().mint(token_contract(), U256::from(1), other);
+    market.host_mut().approve(token_contract(), U256::from(2), other);
+
+    let purse = market.host_mut().new_purse(U512::from(1_000));
+    market.execute(|market| market.buy_floor(buyer, &token_arg(), U512::from(300), purse))
+        .expect("buy_floor should succeed");
+
+    assert_eq!(market.host().owner(token_contract(), U256::from(3)), Some(buyer));
+    assert_eq!(market.host().purse_balance(purse), U512::from(700));
+    assert_eq!(market.get_listing(&token_arg(), "1"), None);
+    assert_eq!(market.get_listing(&token_arg(), "2"), None);
+    assert_eq!(market.get_floor_price(&token_arg()), Ok(None));
+    assert_eq!(market.get_collection_stats(&token_arg()).unwrap().active_listings, 0);
+    assert!(market.get_positions(ACCOUNT_LISTINGS_DICTIONARY, &seller).is_empty());
+}
+
+#[test]
+fn should_keep_closed_stale_listings_closed_when_buy_floor_runs_out_of_attempts() {
+    let mut market = market();
+    let (seller, buyer, other) = (account(1), account(2), account(3));
+    for token_id in 1..=9 {
+        listed(&mut market, seller, token_id, 100 + token_id);
+        if token_id < 9 {
+            market.host_mut().approve(token_contract(), U256::from(token_id), other);
+        }
+    }
+
+    let purse = market.host_mut().new_purse(U512::from(1_000));
+    let buy_floor = |market: &mut Market<MemoryHost>| {
+        market.execute(|market| market.buy_floor(buyer, &token_arg(), U512::from(1_000), purse))
+    };
+    // every attempt finds a stale listing, they're closed & nothing is bought
+    buy_floor(&mut market).expect("buy_floor should succeed");
+    assert_eq!(market.host().purse_balance(purse), U512::from(1_000));
+    assert_eq!(market.get_floor_price(&token_arg()), Ok(Some(U512::from(109))));
+
+    buy_floor(&mut market).expect("buy_floor should succeed");
+    assert_eq!(market.host().owner(token_contract(), U256::from(9)), Some(buyer));
+    assert_eq!(market.host().purse_balance(purse), U512::from(891));
+}
+
+#[test]
+fn should_track_each_accounts_open_positions() {
+    let mut market = market();
+    let (seller, buyer) = (account(1), account(2));
+    for token_id in 1..=4 {
+        listed(&mut market, seller, token_id, 100);
+    }
+    let listing_id = |market: &Market<MemoryHost>, token_id: &str| market.get_id(&token_arg(), token_id);
+    let positions = |market: &mut Market<MemoryHost>| {
+        let mut ids = market.get_positions(ACCOUNT_LISTINGS_DICTIONARY, &seller);
+        ids.sort();
+        ids
+    };
+
+    market.cancel_listing(seller, &token_arg(), "2").expect("cancel should succeed");
+    let purse = market.host_mut().new_purse(U512::from(100));
+    market.execute(|market| market.buy_listing(buyer, &token_arg(), "1", purse))
+        .expect("purchase should succeed");
+    // relisting an open listing doesn't track it twice
+    market.create_listing(seller, &token_arg(), "4", U512::from(200)).expect("update should succeed");
+
+    let mut expected = vec![listing_id(&market, "3"), listing_id(&market, "4")];
+    expected.sort();
+    assert_eq!(positions(&mut market), expected);
+
+    listed(&mut market, seller, 5, 100);
+    expected.push(listing_id(&market, "5"));
+    expected.sort();
+    assert_eq!(positions(&mut market), expected);
+}
+
+#[test]
+fn should_length_prefix_both_parts_of_an_id() {
+    let market = market();
+
+    assert_eq!(market.get_id("a", "b"), market.get_id("a", "b"));
+    assert_ne!(market.get_id("ab", ""), market.get_id("a", "b"));
+    assert_eq!(market.get_id("a", "b").len(), 64);
+    assert_eq!(market.get_key_id(&account(1)).len(), 64);
+}
+
+#[test]
+fn should_reject_malformed_args() {
+    assert_eq!(parse_token_contract_hash(&format!("hash-{}", "00".repeat(32))), Err(Error::InvalidTokenContractHash));
+    assert_eq!(parse_token_contract_hash(&token_arg()), Ok(token_contract()));
+    assert_eq!(parse_token_id("-1"), Err(Error::InvalidTokenId));
+    assert_eq!(parse_token_id("42"), Ok(U256::from(42)));
+    assert_eq!(parse_account_key("account-hash-zz"), Err(Error::InvalidAccountKey));
+    assert_eq!(parse_account_key(&account(1).to_formatted_string()), Ok(account(1)));
+}
+
+#[test]
+fn should_fail_instead_of_overflowing_collection_volume() {
+    let mut market = market();
+    let (seller, buyer) = (account(1), account(2));
+    // no real purse holds this much, MemoryHost lets the volume reach its limit
+    mint(&mut market, seller, 1);
+    market.create_listing(seller, &token_arg(), "1", U512::MAX).expect("listing should succeed");
+    listed(&mut market, seller, 2, 1);
+
+    let purse = market.host_mut().new_purse(U512::MAX);
+    market.execute(|market| market.buy_listing(buyer, &token_arg(), "1", purse))
+        .expect("buy should succeed");
+    assert_eq!(market.get_collection_stats(&token_arg()).unwrap().volume, U512::MAX);
+
+    let purse = market.host_mut().new_purse(U512::one());
+    assert_eq!(
+        market.execute(|market| market.buy_listing(buyer, &token_arg(), "2", purse)),
+        error(Error::ArithmeticOverflow)
+    );
+    assert_eq!(market.host().owner(token_contract(), U256::from(2)), Some(seller));
+}
+
+#[test]
+fn should_map_user_codes_back_to_errors() {
+    assert_eq!(Error::from_code(1000), Some(Error::ListingDoesNotExist));
+    assert_eq!(Error::from_code(Error::ReentrantCall as u16), Some(Error::ReentrantCall));
+    assert_eq!(Error::from_code(999), None);
+}
+
+#[test]
+fn should_decode_every_event_it_emits() {
+    let mut market = market();
+    let (seller, buyer) = (account(1), account(2));
+    listed(&mut market, seller, 1, 100);
+    offer(&mut market, buyer, "1", 150).expect("offer should succeed");
+    market.withdraw_offer(buyer, &token_arg(), "1").expect("withdraw should succeed");
+    offer(&mut market, buyer, "1", 150).expect("offer should succeed");
+    market.accept_offer(seller, &token_arg(), "1", &buyer.to_formatted_string()).expect("accept should succeed");
+    listed(&mut market, seller, 2, 100);
+    market.cancel_listing(seller, &token_arg(), "2").expect("cancel should succeed");
+    market.create_listing(seller, &token_arg(), "2", U512::from(100)).expect("listing should succeed");
+    let purse = market.host_mut().new_purse(U512::from(100));
+    market.execute(|market| market.buy_listing(buyer, &token_arg(), "2", purse)).expect("purchase should succeed");
+
+    let events = market.host().events.clone();
+    let names: BTreeSet<&str> = events.iter().map(MarketEvent::name).collect();
+    assert_eq!(names.len(), 6);
+    for event in events {
+        let bytes = event.to_bytes().unwrap();
+        assert!(schemas().contains(event.name()));
+        assert_eq!(bytesrepr::deserialize::<MarketEvent>(bytes), Ok(event));
+    }
+
+    let unknown = format!("{}Unknown", EVENT_PREFIX).to_bytes().unwrap();
+    assert_eq!(bytesrepr::deserialize::<MarketEvent>(unknown), Err(bytesrepr::Error::Formatting));
+}
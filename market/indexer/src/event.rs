@@ -0,0 +1,16 @@
+use casper_types::bytesrepr::{self, FromBytes};
+use market_core::{schemas, MarketEvent, EVENT_PREFIX};
+
+use crate::Error;
+
+/// Decodes the CES bytes stored in the contract's `__events` dictionary with the market's own
+/// `MarketEvent`, so the indexer reads exactly what the contract writes.
+pub fn from_ces_bytes(bytes: &[u8]) -> Result<MarketEvent, Error> {
+    let (name, _) = String::from_bytes(bytes)?;
+
+    match name.strip_prefix(EVENT_PREFIX) {
+        Some(event) if schemas().contains(event) => Ok(bytesrepr::deserialize(bytes.to_vec())?),
+        Some(event) => Err(Error::UnknownEvent(event.to_string())),
+        None => Err(Error::UnknownEvent(name))
+    }
+}
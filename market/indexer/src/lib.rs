@@ -0,0 +1,78 @@
+//! Off-chain indexer for the market contract. Decodes the contract's Casper Event Standard
+//! events from deploy execution results or its `__events` dictionary & folds them into a local
+//! SQLite model of listings, offers & sales.
+
+use std::fmt;
+
+use casper_types::bytesrepr;
+
+pub mod event;
+pub mod source;
+pub mod store;
+
+pub use event::from_ces_bytes;
+pub use market_core::MarketEvent;
+pub use source::{event_from_dictionary_item, events_from_execution_result, IndexedEvent};
+pub use store::{Listing, Offer, Sale, Store};
+
+#[derive(Debug)]
+pub enum Error {
+    Bytes(bytesrepr::Error),
+    Hex(hex::FromHexError),
+    Json(serde_json::Error),
+    Sqlite(rusqlite::Error),
+    Io(std::io::Error),
+    UnknownEvent(String),
+    MissingField(&'static str),
+    InvalidPrice(String),
+    EventGap { expected: u32, found: u32 }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Bytes(error) => write!(f, "failed to decode event bytes: {}", error),
+            Error::Hex(error) => write!(f, "invalid hex: {}", error),
+            Error::Json(error) => write!(f, "invalid json: {}", error),
+            Error::Sqlite(error) => write!(f, "sqlite error: {}", error),
+            Error::Io(error) => write!(f, "io error: {}", error),
+            Error::UnknownEvent(name) => write!(f, "unknown market event: {}", name),
+            Error::MissingField(field) => write!(f, "missing field: {}", field),
+            Error::InvalidPrice(price) => write!(f, "invalid price: {}", price),
+            Error::EventGap { expected, found } =>
+                write!(f, "missing events {} to {}", expected, found - 1),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<bytesrepr::Error> for Error {
+    fn from(error: bytesrepr::Error) -> Self {
+        Error::Bytes(error)
+    }
+}
+
+impl From<hex::FromHexError> for Error {
+    fn from(error: hex::FromHexError) -> Self {
+        Error::Hex(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Self {
+        Error::Sqlite(error)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error)
+    }
+}
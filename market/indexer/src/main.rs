@@ -0,0 +1,37 @@
+use std::{env, fs, process};
+
+use market_indexer::{events_from_execution_result, Error, Store};
+use serde_json::Value;
+
+const USAGE: &str = "usage: market-indexer <deploy results json> <sqlite db>";
+
+// folds a JSON array of recorded deploy results into the database
+fn run(results_path: &str, database_path: &str) -> Result<(), Error> {
+    let results: Value = serde_json::from_str(&fs::read_to_string(results_path)?)?;
+    let mut store = Store::open(database_path)?;
+
+    let mut events = Vec::new();
+    for result in results.as_array().ok_or(Error::MissingField("deploy results array"))? {
+        events.extend(events_from_execution_result(result, None)?);
+    }
+    store.apply(&events)?;
+
+    for listing in store.listings()? {
+        println!("{} {} listed by {} for {}", listing.token_contract, listing.token_id, listing.seller, listing.price);
+    }
+    println!("next event index: {}", store.next_event_index()?);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 {
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+
+    if let Err(error) = run(&args[1], &args[2]) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,102 @@
+use casper_types::{
+    bytesrepr::{Bytes, FromBytes},
+    CLTyped, CLValue, URefAddr};
+use serde_json::Value;
+
+use market_core::MarketEvent;
+
+use crate::{event::from_ces_bytes, Error};
+
+/// A market event together with its index in the contract's `__events` dictionary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedEvent {
+    pub index: u32,
+    pub event: MarketEvent
+}
+
+/// Pulls the market events out of a deploy's execution result, either a `DeployProcessed`
+/// event stream message or an `info_get_deploy` execution result. Failed deploys have none.
+///
+/// Dictionary writes show up as `Any` values wrapping the stored value, the dictionary's seed
+/// URef & the item key. Events are stored as `List<U8>` values holding the CES bytes. When
+/// `events_dictionary` is given only writes to that dictionary count.
+pub fn events_from_execution_result(
+    value: &Value,
+    events_dictionary: Option<URefAddr>
+) -> Result<Vec<IndexedEvent>, Error> {
+    let mut events = Vec::new();
+
+    for transform in find_transforms(value) {
+        let write = &transform["transform"]["WriteCLValue"];
+        if write["cl_type"] != "Any" {
+            continue;
+        }
+        let bytes = match write["bytes"].as_str() {
+            Some(bytes) => hex::decode(bytes)?,
+            None => continue
+        };
+
+        let (stored, seed_uref, item_key) = match parse_dictionary_value(&bytes) {
+            Ok(parts) => parts,
+            // an `Any` write that isn't a dictionary entry
+            Err(_) => continue
+        };
+
+        if events_dictionary.map_or(false, |events_uref| events_uref != seed_uref) {
+            continue;
+        }
+        if *stored.cl_type() != Bytes::cl_type() {
+            continue;
+        }
+
+        // other dictionaries (listings, offers...) hold typed values, so only keys that are
+        // event indexes & bytes that decode as an event make it through
+        let index = match String::from_utf8(item_key.to_vec()).ok().and_then(|key| key.parse().ok()) {
+            Some(index) => index,
+            None => continue
+        };
+        let event = Bytes::from_bytes(stored.inner_bytes())
+            .map_err(Error::from)
+            .and_then(|(event_bytes, _)| from_ces_bytes(&event_bytes));
+        match event {
+            Ok(event) => events.push(IndexedEvent { index, event }),
+            Err(Error::UnknownEvent(_)) | Err(Error::Bytes(_)) if events_dictionary.is_none() => continue,
+            Err(error) => return Err(error)
+        }
+    }
+
+    events.sort_by_key(|indexed| indexed.index);
+    Ok(events)
+}
+
+/// Decodes a `state_get_dictionary_item` result read from the `__events` dictionary.
+pub fn event_from_dictionary_item(index: u32, value: &Value) -> Result<IndexedEvent, Error> {
+    let bytes = value["stored_value"]["CLValue"]["bytes"]
+        .as_str()
+        .ok_or(Error::MissingField("stored_value.CLValue.bytes"))?;
+
+    let (event_bytes, _) = Bytes::from_bytes(&hex::decode(bytes)?)?;
+    let event = from_ces_bytes(&event_bytes)?;
+    Ok(IndexedEvent { index, event })
+}
+
+fn find_transforms(value: &Value) -> Vec<&Value> {
+    let value = value.get("DeployProcessed").unwrap_or(value);
+
+    let results: Vec<&Value> = match value.get("execution_results") {
+        Some(Value::Array(results)) => results.iter().map(|result| &result["result"]).collect(),
+        _ => vec![&value["execution_result"]]
+    };
+
+    results.into_iter()
+        .filter_map(|result| result["Success"]["effect"]["transforms"].as_array())
+        .flatten()
+        .collect()
+}
+
+fn parse_dictionary_value(bytes: &[u8]) -> Result<(CLValue, URefAddr, Bytes), Error> {
+    let (stored, remainder) = CLValue::from_bytes(bytes)?;
+    let (seed_uref, remainder) = URefAddr::from_bytes(remainder)?;
+    let (item_key, _) = Bytes::from_bytes(remainder)?;
+    Ok((stored, seed_uref, item_key))
+}
@@ -0,0 +1,291 @@
+use std::path::Path;
+
+use casper_types::{ContractHash, Key, U512};
+use market_core::MarketEvent;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{source::IndexedEvent, Error};
+
+pub const SALE_TYPE_LISTING: &str = "listing";
+pub const SALE_TYPE_OFFER: &str = "offer";
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS listings (
+        token_contract TEXT NOT NULL,
+        token_id TEXT NOT NULL,
+        seller TEXT NOT NULL,
+        price TEXT NOT NULL,
+        PRIMARY KEY (token_contract, token_id)
+    );
+    CREATE TABLE IF NOT EXISTS offers (
+        token_contract TEXT NOT NULL,
+        token_id TEXT NOT NULL,
+        buyer TEXT NOT NULL,
+        price TEXT NOT NULL,
+        PRIMARY KEY (token_contract, token_id, buyer)
+    );
+    CREATE TABLE IF NOT EXISTS sales (
+        event_index INTEGER PRIMARY KEY,
+        token_contract TEXT NOT NULL,
+        token_id TEXT NOT NULL,
+        seller TEXT NOT NULL,
+        buyer TEXT NOT NULL,
+        price TEXT NOT NULL,
+        sale_type TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS events (
+        event_index INTEGER PRIMARY KEY,
+        name TEXT NOT NULL
+    );
+";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Listing {
+    pub token_contract: String,
+    pub token_id: String,
+    pub seller: String,
+    pub price: U512
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Offer {
+    pub token_contract: String,
+    pub token_id: String,
+    pub buyer: String,
+    pub price: U512
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sale {
+    pub event_index: u32,
+    pub token_contract: String,
+    pub token_id: String,
+    pub seller: String,
+    pub buyer: String,
+    pub price: U512,
+    pub sale_type: String
+}
+
+/// Local SQLite model of the market's listings, offers & sales, built by folding its events.
+pub struct Store {
+    connection: Connection
+}
+
+impl Store {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Store, Error> {
+        Store::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Store, Error> {
+        Store::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> Result<Store, Error> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Store { connection })
+    }
+
+    /// The index the next event is expected to have.
+    pub fn next_event_index(&self) -> Result<u32, Error> {
+        let last: Option<u32> = self.connection
+            .query_row("SELECT MAX(event_index) FROM events", [], |row| row.get(0))?;
+        Ok(last.map_or(0, |index| index + 1))
+    }
+
+    /// Applies the events in index order. Events that were already applied are skipped & a
+    /// missing index is reported as a gap so the caller can backfill from the event dictionary.
+    pub fn apply(&mut self, events: &[IndexedEvent]) -> Result<(), Error> {
+        let mut sorted: Vec<&IndexedEvent> = events.iter().collect();
+        sorted.sort_by_key(|indexed| indexed.index);
+
+        let transaction = self.connection.transaction()?;
+        let last: Option<u32> = transaction
+            .query_row("SELECT MAX(event_index) FROM events", [], |row| row.get(0))?;
+        let mut expected = last.map_or(0, |index| index + 1);
+
+        for indexed in sorted {
+            if indexed.index < expected {
+                continue;
+            }
+            if indexed.index > expected {
+                return Err(Error::EventGap { expected, found: indexed.index });
+            }
+            fold(&transaction, indexed)?;
+            expected += 1;
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+
+    pub fn listing(&self, token_contract: &ContractHash, token_id: &str) -> Result<Option<Listing>, Error> {
+        let listing = self.connection.query_row(
+            "SELECT token_contract, token_id, seller, price FROM listings
+                WHERE token_contract = ?1 AND token_id = ?2",
+            params![contract_string(token_contract), token_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get::<_, String>(3)?))
+        ).optional()?;
+
+        listing.map(|(token_contract, token_id, seller, price)| Ok(Listing {
+            token_contract,
+            token_id,
+            seller,
+            price: parse_price(&price)?
+        })).transpose()
+    }
+
+    pub fn listings(&self) -> Result<Vec<Listing>, Error> {
+        let mut statement = self.connection.prepare(
+            "SELECT token_contract, token_id, seller, price FROM listings
+                ORDER BY token_contract, token_id"
+        )?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get::<_, String>(3)?))
+        })?;
+
+        let listings: Result<Vec<Listing>, Error> = rows.map(|row| {
+            let (token_contract, token_id, seller, price) = row?;
+            Ok(Listing { token_contract, token_id, seller, price: parse_price(&price)? })
+        }).collect();
+        listings
+    }
+
+    pub fn offers(&self, token_contract: &ContractHash, token_id: &str) -> Result<Vec<Offer>, Error> {
+        let mut statement = self.connection.prepare(
+            "SELECT token_contract, token_id, buyer, price FROM offers
+                WHERE token_contract = ?1 AND token_id = ?2 ORDER BY buyer"
+        )?;
+        let rows = statement.query_map(params![contract_string(token_contract), token_id], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get::<_, String>(3)?))
+        })?;
+
+        let offers: Result<Vec<Offer>, Error> = rows.map(|row| {
+            let (token_contract, token_id, buyer, price) = row?;
+            Ok(Offer { token_contract, token_id, buyer, price: parse_price(&price)? })
+        }).collect();
+        offers
+    }
+
+    pub fn sales(&self, token_contract: &ContractHash, token_id: &str) -> Result<Vec<Sale>, Error> {
+        let mut statement = self.connection.prepare(
+            "SELECT event_index, token_contract, token_id, seller, buyer, price, sale_type FROM sales
+                WHERE token_contract = ?1 AND token_id = ?2 ORDER BY event_index"
+        )?;
+        let rows = statement.query_map(params![contract_string(token_contract), token_id], |row| {
+            Ok((
+                row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?,
+                row.get(4)?, row.get::<_, String>(5)?, row.get(6)?
+            ))
+        })?;
+
+        let sales: Result<Vec<Sale>, Error> = rows.map(|row| {
+            let (event_index, token_contract, token_id, seller, buyer, price, sale_type) = row?;
+            Ok(Sale {
+                event_index,
+                token_contract,
+                token_id,
+                seller,
+                buyer,
+                price: parse_price(&price)?,
+                sale_type
+            })
+        }).collect();
+        sales
+    }
+}
+
+fn fold(connection: &Connection, indexed: &IndexedEvent) -> Result<(), Error> {
+    connection.execute(
+        "INSERT INTO events (event_index, name) VALUES (?1, ?2)",
+        params![indexed.index, indexed.event.name()]
+    )?;
+
+    match &indexed.event {
+        MarketEvent::ListingCreated { seller, token_contract, token_id, price } => {
+            connection.execute(
+                "INSERT OR REPLACE INTO listings (token_contract, token_id, seller, price)
+                    VALUES (?1, ?2, ?3, ?4)",
+                params![contract_string(token_contract), token_id, key_string(seller), price.to_string()]
+            )?;
+        },
+        MarketEvent::ListingPurchased { seller, buyer, token_contract, token_id, price } => {
+            remove_listing(connection, token_contract, token_id)?;
+            insert_sale(connection, indexed.index, seller, buyer, token_contract, token_id, price, SALE_TYPE_LISTING)?;
+        },
+        MarketEvent::ListingCanceled { token_contract, token_id } => {
+            remove_listing(connection, token_contract, token_id)?;
+        },
+        MarketEvent::OfferCreated { buyer, token_contract, token_id, price } => {
+            connection.execute(
+                "INSERT OR REPLACE INTO offers (token_contract, token_id, buyer, price)
+                    VALUES (?1, ?2, ?3, ?4)",
+                params![contract_string(token_contract), token_id, key_string(buyer), price.to_string()]
+            )?;
+        },
+        MarketEvent::OfferWithdraw { buyer, token_contract, token_id } => {
+            connection.execute(
+                "DELETE FROM offers WHERE token_contract = ?1 AND token_id = ?2 AND buyer = ?3",
+                params![contract_string(token_contract), token_id, key_string(buyer)]
+            )?;
+        },
+        // the contract settles only the accepted bid & drops any listing when an offer is
+        // accepted, the other bids stay open until their bidders withdraw them
+        MarketEvent::OfferAccepted { seller, buyer, token_contract, token_id, price } => {
+            connection.execute(
+                "DELETE FROM offers WHERE token_contract = ?1 AND token_id = ?2 AND buyer = ?3",
+                params![contract_string(token_contract), token_id, key_string(buyer)]
+            )?;
+            remove_listing(connection, token_contract, token_id)?;
+            insert_sale(connection, indexed.index, seller, buyer, token_contract, token_id, price, SALE_TYPE_OFFER)?;
+        },
+    }
+    Ok(())
+}
+
+fn remove_listing(connection: &Connection, token_contract: &ContractHash, token_id: &str) -> Result<(), Error> {
+    connection.execute(
+        "DELETE FROM listings WHERE token_contract = ?1 AND token_id = ?2",
+        params![contract_string(token_contract), token_id]
+    )?;
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn insert_sale(
+    connection: &Connection,
+    event_index: u32,
+    seller: &Key,
+    buyer: &Key,
+    token_contract: &ContractHash,
+    token_id: &str,
+    price: &U512,
+    sale_type: &str
+) -> Result<(), Error> {
+    connection.execute(
+        "INSERT INTO sales (event_index, token_contract, token_id, seller, buyer, price, sale_type)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            event_index,
+            contract_string(token_contract),
+            token_id,
+            key_string(seller),
+            key_string(buyer),
+            price.to_string(),
+            sale_type
+        ]
+    )?;
+    Ok(())
+}
+
+// stored in the same formatted form clients pass as `token_contract_hash`
+fn contract_string(token_contract: &ContractHash) -> String {
+    token_contract.to_formatted_string()
+}
+
+fn key_string(key: &Key) -> String {
+    key.to_formatted_string()
+}
+
+fn parse_price(price: &str) -> Result<U512, Error> {
+    U512::from_dec_str(price).map_err(|_| Error::InvalidPrice(price.to_string()))
+}
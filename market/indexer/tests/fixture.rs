@@ -0,0 +1,175 @@
+use std::{convert::TryInto, fs};
+
+use casper_types::{
+    bytesrepr::{Bytes, ToBytes},
+    ContractHash, Key, URefAddr, U512};
+use market_indexer::{
+    event_from_dictionary_item, events_from_execution_result,
+    from_ces_bytes, Error, IndexedEvent, MarketEvent, Store};
+use serde_json::{json, Value};
+
+// deploy results shaped like the node's, hand-written until `make indexer-fixture` from `market`
+// records them from an engine run. `recording.json` holds the accounts, collection & events
+// dictionary they use
+const FIXTURE: &str = "tests/fixtures/deploy_results.json";
+const RECORDING: &str = "tests/fixtures/recording.json";
+
+fn recording() -> Value {
+    serde_json::from_str(&fs::read_to_string(RECORDING).unwrap()).unwrap()
+}
+
+fn account(name: &str) -> Key {
+    Key::from_formatted_str(recording()["accounts"][name].as_str().unwrap()).unwrap()
+}
+
+fn token_contract() -> ContractHash {
+    ContractHash::from_formatted_str(recording()["token_contract"].as_str().unwrap()).unwrap()
+}
+
+fn events_dictionary() -> URefAddr {
+    hex::decode(recording()["events_dictionary"].as_str().unwrap()).unwrap().try_into().unwrap()
+}
+
+fn motes(cspr: u64) -> U512 {
+    U512::from(cspr) * U512::from(1_000_000_000u64)
+}
+
+fn fixture_events(events_dictionary: Option<[u8; 32]>) -> Vec<IndexedEvent> {
+    let results: Value = serde_json::from_str(&fs::read_to_string(FIXTURE).unwrap()).unwrap();
+
+    results.as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|result| events_from_execution_result(result, events_dictionary).unwrap())
+        .collect()
+}
+
+#[test]
+fn should_decode_every_event_in_the_fixture() {
+    let events = fixture_events(Some(events_dictionary()));
+
+    let indexes: Vec<u32> = events.iter().map(|indexed| indexed.index).collect();
+    assert_eq!(indexes, (0..10).collect::<Vec<u32>>());
+
+    assert_eq!(events[0].event, MarketEvent::ListingCreated {
+        seller: account("seller"),
+        token_contract: token_contract(),
+        token_id: "1".to_string(),
+        price: motes(100)
+    });
+    assert_eq!(events[5].event, MarketEvent::OfferAccepted {
+        seller: account("seller"),
+        buyer: account("other"),
+        token_contract: token_contract(),
+        token_id: "1".to_string(),
+        price: motes(90)
+    });
+    assert_eq!(events[8].event, MarketEvent::OfferWithdraw {
+        buyer: account("bidder"),
+        token_contract: token_contract(),
+        token_id: "2".to_string()
+    });
+}
+
+#[test]
+fn should_skip_other_dictionary_writes_without_a_filter() {
+    assert_eq!(fixture_events(None), fixture_events(Some(events_dictionary())));
+}
+
+#[test]
+fn should_ignore_writes_to_other_dictionaries() {
+    assert!(fixture_events(Some([0x01; 32])).is_empty());
+}
+
+#[test]
+fn should_fold_events_into_listings_offers_and_sales() {
+    let mut store = Store::open_in_memory().unwrap();
+    store.apply(&fixture_events(None)).unwrap();
+
+    // token 1 sold through an accepted offer, token 2 was bought & relisted by the buyer
+    assert_eq!(store.listing(&token_contract(), "1").unwrap(), None);
+    let listing = store.listing(&token_contract(), "2").unwrap().unwrap();
+    assert_eq!(listing.seller, account("buyer").to_formatted_string());
+    assert_eq!(listing.price, motes(70));
+    assert_eq!(store.listings().unwrap().len(), 1);
+
+    // accepting an offer settles only that bid, the other bidder's stays open
+    let offers = store.offers(&token_contract(), "1").unwrap();
+    assert_eq!(offers.len(), 1);
+    assert_eq!(offers[0].buyer, account("bidder").to_formatted_string());
+    assert_eq!(offers[0].price, motes(80));
+    let offers = store.offers(&token_contract(), "2").unwrap();
+    assert_eq!(offers.len(), 1);
+    assert_eq!(offers[0].buyer, account("bidder").to_formatted_string());
+    assert_eq!(offers[0].price, motes(65));
+
+    let sales = store.sales(&token_contract(), "1").unwrap();
+    assert_eq!(sales.len(), 1);
+    assert_eq!(sales[0].buyer, account("other").to_formatted_string());
+    assert_eq!(sales[0].price, motes(90));
+    assert_eq!(sales[0].sale_type, "offer");
+
+    let sales = store.sales(&token_contract(), "2").unwrap();
+    assert_eq!(sales.len(), 1);
+    assert_eq!(sales[0].event_index, 4);
+    assert_eq!(sales[0].sale_type, "listing");
+
+    assert_eq!(store.next_event_index().unwrap(), 10);
+}
+
+#[test]
+fn should_skip_events_already_applied() {
+    let events = fixture_events(None);
+    let mut store = Store::open_in_memory().unwrap();
+
+    store.apply(&events[..6]).unwrap();
+    store.apply(&events).unwrap();
+
+    assert_eq!(store.next_event_index().unwrap(), 10);
+    assert_eq!(store.sales(&token_contract(), "1").unwrap().len(), 1);
+}
+
+#[test]
+fn should_report_gaps_in_the_event_log() {
+    let events = fixture_events(None);
+    let mut store = Store::open_in_memory().unwrap();
+
+    store.apply(&events[..3]).unwrap();
+    match store.apply(&events[5..]) {
+        Err(Error::EventGap { expected, found }) => assert_eq!((expected, found), (3, 5)),
+        other => panic!("expected a gap, got {:?}", other)
+    }
+    // nothing from the rejected batch is kept
+    assert_eq!(store.next_event_index().unwrap(), 3);
+}
+
+#[test]
+fn should_decode_an_event_read_from_the_dictionary() {
+    let mut bytes = "event_ListingCanceled".to_string().to_bytes().unwrap();
+    bytes.append(&mut token_contract().to_bytes().unwrap());
+    bytes.append(&mut "7".to_string().to_bytes().unwrap());
+    let stored = Bytes::from(bytes.clone()).to_bytes().unwrap();
+
+    let item = json!({
+        "stored_value": {
+            "CLValue": { "cl_type": { "List": "U8" }, "bytes": hex::encode(stored), "parsed": bytes }
+        }
+    });
+
+    let indexed = event_from_dictionary_item(12, &item).unwrap();
+    assert_eq!(indexed.index, 12);
+    assert_eq!(indexed.event, MarketEvent::ListingCanceled {
+        token_contract: token_contract(),
+        token_id: "7".to_string()
+    });
+}
+
+#[test]
+fn should_reject_unknown_events() {
+    let bytes = "event_Unknown".to_string().to_bytes().unwrap();
+
+    match from_ces_bytes(&bytes) {
+        Err(Error::UnknownEvent(name)) => assert_eq!(name, "Unknown"),
+        other => panic!("expected an unknown event, got {:?}", other)
+    }
+}
@@ -0,0 +1,266 @@
+//! A test-only token exposing the CEP-47 entry points the market calls (`owner_of`,
+//! `get_approved` & `transfer_from`) plus `mint` & `approve` to set up state. `configure` makes it
+//! lie about ownership, hand back arbitrary approval keys, revert or skip transfers, or call back
+//! into the market from `transfer_from`, so the market tests can check what a hostile collection
+//! can & can't do. Never deploy it anywhere but the in-memory engine.
+
+#![no_std]
+#![no_main]
+
+#[cfg(not(target_arch = "wasm32"))]
+compile_error!("target arch should be wasm32: compile with '--target wasm32-unknown-unknown'");
+
+// We need to explicitly import the std alloc crate and `alloc::string::String` as we're in a
+// `no_std` environment.
+extern crate alloc;
+
+use alloc::{
+    string::{String, ToString},
+    vec, vec::Vec,
+    collections::BTreeMap
+};
+
+use casper_contract::{
+    contract_api::{runtime, storage},
+    unwrap_or_revert::UnwrapOrRevert,
+};
+use casper_types::{
+    bytesrepr::{FromBytes, ToBytes},
+    contracts::{EntryPoint, EntryPointAccess, EntryPointType, EntryPoints, NamedKeys},
+    system::CallStackElement,
+    runtime_args, RuntimeArgs,
+    ApiError, CLType, CLTyped, CLValue, ContractHash, Key, Parameter, URef, U256};
+
+const CONTRACT_NAME: &str = "mock_token";
+
+const OWNERS_DICTIONARY: &str = "owners";
+const APPROVALS_DICTIONARY: &str = "approvals";
+
+// misbehavior config, all of it replaced by every `configure` call
+const OWNER_OVERRIDE: &str = "owner_override";
+const APPROVED_OVERRIDE: &str = "approved_override";
+const TRANSFER_MODE: &str = "transfer_mode";
+const REENTER_MARKET: &str = "reenter_market";
+const REENTER_ENTRY_POINT: &str = "reenter_entry_point";
+const REENTER_OFFER: &str = "reenter_offer";
+
+// moves the tokens like a CEP-47 contract would
+const TRANSFER_HONEST: u8 = 0;
+// reverts, as if the transfer wasn't allowed
+const TRANSFER_REVERT: u8 = 1;
+// returns without moving anything
+const TRANSFER_IGNORE: u8 = 2;
+// calls `reenter_entry_point` on the market once, then transfers honestly
+const TRANSFER_REENTER: u8 = 3;
+
+// `transfer_from` revert code in TRANSFER_REVERT mode, so tests can tell it from the market's
+const TRANSFER_REFUSED: u16 = 1;
+
+fn read_config<T: CLTyped + FromBytes>(name: &str) -> T {
+    let uref: URef = runtime::get_key(name)
+        .and_then(Key::into_uref)
+        .unwrap_or_revert_with(ApiError::MissingKey);
+    storage::read(uref)
+        .unwrap_or_revert()
+        .unwrap_or_revert_with(ApiError::ValueNotFound)
+}
+
+fn write_config<T: CLTyped + ToBytes>(name: &str, value: T) -> () {
+    let uref: URef = runtime::get_key(name)
+        .and_then(Key::into_uref)
+        .unwrap_or_revert_with(ApiError::MissingKey);
+    storage::write(uref, value);
+}
+
+fn get_dictionary_uref(key: &str) -> URef {
+    match runtime::get_key(key) {
+        Some(uref_key) => uref_key.into_uref().unwrap_or_revert(),
+        None => storage::new_dictionary(key).unwrap_or_revert(),
+    }
+}
+
+fn read_owner(token_id: &U256) -> Option<Key> {
+    storage::dictionary_get(get_dictionary_uref(OWNERS_DICTIONARY), &token_id.to_string())
+        .unwrap_or_revert()
+}
+
+fn this_contract_hash() -> ContractHash {
+    match runtime::get_call_stack().last().unwrap_or_revert() {
+        CallStackElement::StoredContract { contract_hash, .. } => *contract_hash,
+        _ => runtime::revert(ApiError::InvalidCallerInfoRequest)
+    }
+}
+
+// takes CEP-47's args so the market fixture's helpers drive it unchanged, metas are ignored
+#[no_mangle]
+pub extern "C" fn mint() -> () {
+    let recipient: Key = runtime::get_named_arg("recipient");
+    let token_ids: Vec<U256> = runtime::get_named_arg("token_ids");
+    let owners_uref = get_dictionary_uref(OWNERS_DICTIONARY);
+    for token_id in token_ids {
+        storage::dictionary_put(owners_uref, &token_id.to_string(), recipient);
+    }
+}
+
+// anyone may approve, the tests only care about what `get_approved` hands the market
+#[no_mangle]
+pub extern "C" fn approve() -> () {
+    let spender: Key = runtime::get_named_arg("spender");
+    let token_ids: Vec<U256> = runtime::get_named_arg("token_ids");
+    let approvals_uref = get_dictionary_uref(APPROVALS_DICTIONARY);
+    for token_id in token_ids {
+        storage::dictionary_put(approvals_uref, &token_id.to_string(), Some(spender));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn owner_of() -> () {
+    let token_id: U256 = runtime::get_named_arg("token_id");
+    let owner: Option<Key> = read_config::<Option<Key>>(OWNER_OVERRIDE)
+        .or_else(|| read_owner(&token_id));
+    runtime::ret(CLValue::from_t(owner).unwrap_or_revert())
+}
+
+#[no_mangle]
+pub extern "C" fn get_approved() -> () {
+    let token_id: U256 = runtime::get_named_arg("token_id");
+    let approved: Option<Key> = match read_config::<Option<Key>>(APPROVED_OVERRIDE) {
+        Some(key) => Some(key),
+        None => storage::dictionary_get::<Option<Key>>(get_dictionary_uref(APPROVALS_DICTIONARY), &token_id.to_string())
+            .unwrap_or_revert()
+            .flatten()
+    };
+    runtime::ret(CLValue::from_t(approved).unwrap_or_revert())
+}
+
+#[no_mangle]
+pub extern "C" fn transfer_from() -> () {
+    let sender: Key = runtime::get_named_arg("sender");
+    let recipient: Key = runtime::get_named_arg("recipient");
+    let token_ids: Vec<U256> = runtime::get_named_arg("token_ids");
+
+    match read_config::<u8>(TRANSFER_MODE) {
+        TRANSFER_REVERT => runtime::revert(ApiError::User(TRANSFER_REFUSED)),
+        TRANSFER_IGNORE => return,
+        TRANSFER_REENTER => reenter_market(&token_ids),
+        _ => ()
+    }
+
+    let owners_uref = get_dictionary_uref(OWNERS_DICTIONARY);
+    let approvals_uref = get_dictionary_uref(APPROVALS_DICTIONARY);
+    for token_id in token_ids {
+        if read_owner(&token_id) != Some(sender) {
+            runtime::revert(ApiError::PermissionDenied);
+        }
+        storage::dictionary_put(owners_uref, &token_id.to_string(), recipient);
+        storage::dictionary_put(approvals_uref, &token_id.to_string(), None::<Key>);
+    }
+}
+
+// disarmed first so a nested `transfer_from` doesn't re-enter again, the market sees this
+// contract's package as the caller
+fn reenter_market(token_ids: &[U256]) -> () {
+    write_config(TRANSFER_MODE, TRANSFER_HONEST);
+
+    let market: ContractHash = read_config::<Option<ContractHash>>(REENTER_MARKET)
+        .unwrap_or_revert_with(ApiError::MissingArgument);
+    let entry_point: String = read_config(REENTER_ENTRY_POINT);
+    let token_id: U256 = token_ids.first().copied().unwrap_or_revert();
+
+    runtime::call_contract::<()>(market, &entry_point, runtime_args! {
+        "token_contract_hash" => this_contract_hash().to_formatted_string(),
+        "token_id" => token_id.to_string(),
+        "accepted_offer" => read_config::<String>(REENTER_OFFER)
+    });
+}
+
+#[no_mangle]
+pub extern "C" fn configure() -> () {
+    write_config(OWNER_OVERRIDE, runtime::get_named_arg::<Option<Key>>(OWNER_OVERRIDE));
+    write_config(APPROVED_OVERRIDE, runtime::get_named_arg::<Option<Key>>(APPROVED_OVERRIDE));
+    write_config(TRANSFER_MODE, runtime::get_named_arg::<u8>(TRANSFER_MODE));
+    write_config(REENTER_MARKET, runtime::get_named_arg::<Option<ContractHash>>(REENTER_MARKET));
+    write_config(REENTER_ENTRY_POINT, runtime::get_named_arg::<String>(REENTER_ENTRY_POINT));
+    write_config(REENTER_OFFER, runtime::get_named_arg::<String>(REENTER_OFFER));
+}
+
+fn public_entry_point(name: &str, params: Vec<Parameter>, ret: CLType) -> EntryPoint {
+    EntryPoint::new(
+        name,
+        params,
+        ret,
+        EntryPointAccess::Public,
+        EntryPointType::Contract,
+    )
+}
+
+fn get_entry_points() -> EntryPoints {
+    let mut entry_points = EntryPoints::new();
+
+    entry_points.add_entry_point(public_entry_point(
+        "mint",
+        vec![
+            Parameter::new("recipient", Key::cl_type()),
+            Parameter::new("token_ids", Vec::<U256>::cl_type()),
+            Parameter::new("token_metas", Vec::<BTreeMap<String, String>>::cl_type())
+        ],
+        <()>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "approve",
+        vec![Parameter::new("spender", Key::cl_type()), Parameter::new("token_ids", Vec::<U256>::cl_type())],
+        <()>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "owner_of",
+        vec![Parameter::new("token_id", U256::cl_type())],
+        Option::<Key>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "get_approved",
+        vec![Parameter::new("owner", Key::cl_type()), Parameter::new("token_id", U256::cl_type())],
+        Option::<Key>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "transfer_from",
+        vec![
+            Parameter::new("sender", Key::cl_type()),
+            Parameter::new("recipient", Key::cl_type()),
+            Parameter::new("token_ids", Vec::<U256>::cl_type())
+        ],
+        <()>::cl_type()
+    ));
+    entry_points.add_entry_point(public_entry_point(
+        "configure",
+        vec![
+            Parameter::new(OWNER_OVERRIDE, Option::<Key>::cl_type()),
+            Parameter::new(APPROVED_OVERRIDE, Option::<Key>::cl_type()),
+            Parameter::new(TRANSFER_MODE, u8::cl_type()),
+            Parameter::new(REENTER_MARKET, Option::<ContractHash>::cl_type()),
+            Parameter::new(REENTER_ENTRY_POINT, String::cl_type()),
+            Parameter::new(REENTER_OFFER, String::cl_type())
+        ],
+        <()>::cl_type()
+    ));
+    entry_points
+}
+
+// installs honest, tests flip it with `configure`
+#[no_mangle]
+pub extern "C" fn call() {
+    let mut named_keys = NamedKeys::new();
+    named_keys.insert(String::from(OWNER_OVERRIDE), storage::new_uref(None::<Key>).into());
+    named_keys.insert(String::from(APPROVED_OVERRIDE), storage::new_uref(None::<Key>).into());
+    named_keys.insert(String::from(TRANSFER_MODE), storage::new_uref(TRANSFER_HONEST).into());
+    named_keys.insert(String::from(REENTER_MARKET), storage::new_uref(None::<ContractHash>).into());
+    named_keys.insert(String::from(REENTER_ENTRY_POINT), storage::new_uref(String::new()).into());
+    named_keys.insert(String::from(REENTER_OFFER), storage::new_uref(String::new()).into());
+
+    let (contract_hash, _) = storage::new_contract(
+        get_entry_points(),
+        Some(named_keys),
+        Some(String::from(CONTRACT_NAME) + "_package_hash"),
+        Some(String::from(CONTRACT_NAME) + "_access_uref")
+    );
+    runtime::put_key(&(String::from(CONTRACT_NAME) + "_contract_hash"), contract_hash.into());
+}
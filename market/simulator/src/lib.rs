@@ -0,0 +1,60 @@
+//! Local marketplace simulator. Installs CEP-47 & the market in the in-memory execution engine,
+//! runs a scenario of mints, listings, purchases & offers against them & reports the balances,
+//! token owners & market events it ends with. Runs are deterministic, no node is needed.
+
+use std::{fmt, io};
+
+pub mod scenario;
+pub mod simulator;
+
+pub use scenario::{Actor, Mint, Scenario, Step};
+pub use simulator::{Report, Simulator, StepOutcome, Wasm};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Yaml(serde_yaml::Error),
+    Event(market_indexer::Error),
+    Scenario(String),
+    Setup(String)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "io error: {}", error),
+            Error::Json(error) => write!(f, "invalid json scenario: {}", error),
+            Error::Yaml(error) => write!(f, "invalid yaml scenario: {}", error),
+            Error::Event(error) => write!(f, "unreadable market event: {}", error),
+            Error::Scenario(error) => write!(f, "invalid scenario: {}", error),
+            Error::Setup(error) => write!(f, "setup failed: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(error: serde_yaml::Error) -> Self {
+        Error::Yaml(error)
+    }
+}
+
+impl From<market_indexer::Error> for Error {
+    fn from(error: market_indexer::Error) -> Self {
+        Error::Event(error)
+    }
+}
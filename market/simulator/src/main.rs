@@ -0,0 +1,30 @@
+use std::{env, process};
+
+use market_simulator::{Error, Scenario, Simulator, Wasm};
+
+const USAGE: &str = "usage: market-simulator <scenario yaml or json> [wasm directory]";
+// where `make tests-wasm` leaves the wasm, relative to `market/simulator`
+const DEFAULT_WASM_DIRECTORY: &str = "../tests/wasm";
+
+fn run(scenario_path: &str, wasm_directory: &str) -> Result<(), Error> {
+    let scenario = Scenario::load(scenario_path)?;
+    let wasm = Wasm::load(wasm_directory)?;
+
+    let report = Simulator::new(wasm, scenario)?.run()?;
+    print!("{}", report);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+
+    let wasm_directory = args.get(2).map_or(DEFAULT_WASM_DIRECTORY, String::as_str);
+    if let Err(error) = run(&args[1], wasm_directory) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,154 @@
+use std::{collections::BTreeSet, fmt, fs, path::Path};
+
+use serde::Deserialize;
+
+use crate::Error;
+
+// the same defaults the integration tests install the market with
+const DEFAULT_MAX_OFFERS_PER_TOKEN: u32 = 3;
+const DEFAULT_MIN_OFFER_AMOUNT: u64 = 1_000_000_000;
+// 100k CSPR
+const DEFAULT_BALANCE: u64 = 100_000_000_000_000;
+
+/// What to run: who takes part, which tokens they start with & what they do, in order.
+/// Amounts are in motes.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default = "default_max_offers_per_token")]
+    pub max_offers_per_token: u32,
+    #[serde(default = "default_min_offer_amount")]
+    pub min_offer_amount: u64,
+    pub actors: Vec<Actor>,
+    #[serde(default)]
+    pub mints: Vec<Mint>,
+    #[serde(default)]
+    pub steps: Vec<Step>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Actor {
+    pub name: String,
+    /// What the account is funded with before the first step.
+    #[serde(default = "default_balance")]
+    pub balance: u64
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Mint {
+    pub owner: String,
+    pub token_id: String
+}
+
+/// One deploy. `list` approves the market for the token first, `buy`, `buy_floor` & `offer`
+/// go through the payment wasm with `amount` in the purse.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Step {
+    List { actor: String, token_id: String, price: u64 },
+    Cancel { actor: String, token_id: String },
+    Buy { actor: String, token_id: String, amount: u64 },
+    BuyFloor { actor: String, amount: u64 },
+    Offer { actor: String, token_id: String, amount: u64 },
+    Withdraw { actor: String, token_id: String },
+    Accept { actor: String, token_id: String, bidder: String },
+    Claim { actor: String }
+}
+
+fn default_max_offers_per_token() -> u32 {
+    DEFAULT_MAX_OFFERS_PER_TOKEN
+}
+
+fn default_min_offer_amount() -> u64 {
+    DEFAULT_MIN_OFFER_AMOUNT
+}
+
+fn default_balance() -> u64 {
+    DEFAULT_BALANCE
+}
+
+impl Step {
+    pub fn actor(&self) -> &str {
+        match self {
+            Step::List { actor, .. }
+            | Step::Cancel { actor, .. }
+            | Step::Buy { actor, .. }
+            | Step::BuyFloor { actor, .. }
+            | Step::Offer { actor, .. }
+            | Step::Withdraw { actor, .. }
+            | Step::Accept { actor, .. }
+            | Step::Claim { actor } => actor,
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::List { actor, token_id, price } => write!(f, "{} lists {} for {}", actor, token_id, price),
+            Step::Cancel { actor, token_id } => write!(f, "{} cancels the listing of {}", actor, token_id),
+            Step::Buy { actor, token_id, amount } => write!(f, "{} buys {} paying up to {}", actor, token_id, amount),
+            Step::BuyFloor { actor, amount } => write!(f, "{} buys the floor paying up to {}", actor, amount),
+            Step::Offer { actor, token_id, amount } => write!(f, "{} offers {} for {}", actor, amount, token_id),
+            Step::Withdraw { actor, token_id } => write!(f, "{} withdraws their offer for {}", actor, token_id),
+            Step::Accept { actor, token_id, bidder } => write!(f, "{} accepts {}'s offer for {}", actor, bidder, token_id),
+            Step::Claim { actor } => write!(f, "{} claims", actor),
+        }
+    }
+}
+
+impl Scenario {
+    pub fn from_json(contents: &str) -> Result<Scenario, Error> {
+        let scenario: Scenario = serde_json::from_str(contents)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn from_yaml(contents: &str) -> Result<Scenario, Error> {
+        let scenario: Scenario = serde_yaml::from_str(contents)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Reads `.yaml` & `.yml` files as YAML, anything else as JSON.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Scenario, Error> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("yaml") | Some("yml") => Scenario::from_yaml(&contents),
+            _ => Scenario::from_json(&contents)
+        }
+    }
+
+    // every name a mint or step refers to has to be one of the actors
+    fn validate(&self) -> Result<(), Error> {
+        if self.actors.is_empty() {
+            return Err(Error::Scenario("no actors".to_string()));
+        }
+
+        let mut names = BTreeSet::new();
+        for actor in &self.actors {
+            if !names.insert(actor.name.as_str()) {
+                return Err(Error::Scenario(format!("actor {} is declared twice", actor.name)));
+            }
+        }
+
+        let known = |name: &str| if names.contains(name) {
+            Ok(())
+        } else {
+            Err(Error::Scenario(format!("unknown actor {}", name)))
+        };
+        for mint in &self.mints {
+            known(&mint.owner)?;
+        }
+        for step in &self.steps {
+            known(step.actor())?;
+            if let Step::Accept { bidder, .. } = step {
+                known(bidder)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,389 @@
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+
+use blake2::{
+    digest::{Update, VariableOutput},
+    VarBlake2b
+};
+use casper_engine_test_support::{
+    ExecuteRequestBuilder, InMemoryWasmTestBuilder, DEFAULT_ACCOUNT_ADDR, DEFAULT_RUN_GENESIS_REQUEST
+};
+use casper_execution_engine::core::{
+    engine_state::{Error as EngineError, ExecuteRequest},
+    execution::Error as ExecError
+};
+use casper_types::{
+    account::AccountHash,
+    bytesrepr::{Bytes, ToBytes},
+    runtime_args, system::mint, ApiError, CLValue, ContractHash, ContractPackageHash, Key,
+    RuntimeArgs, URef, U256, U512
+};
+use market_client::market::{
+    ACCEPTED_OFFER_ARG, AMOUNT_ARG, CLAIM_PURSE_ARG, MARKET_CONTRACT_HASH_ARG, MARKET_ENTRY_POINT_NAME_ARG,
+    MAX_OFFERS_PER_TOKEN_ARG, MIN_OFFER_AMOUNT_ARG, NFT_CONTRACT_HASH_ARG, PRICE_ARG, TOKEN_ID_ARG
+};
+use market_core::{Error as MarketError, MarketEvent, EVENTS_DICT, EVENTS_LENGTH};
+use market_indexer::from_ces_bytes;
+
+use crate::{
+    scenario::{Scenario, Step},
+    Error
+};
+
+pub const MARKET_WASM: &str = "market.wasm";
+pub const PAYMENT_WASM: &str = "payment.wasm";
+pub const CEP47_WASM: &str = "cep47-token.wasm";
+
+const CEP47_CONTRACT_NAME: &str = "simulator_nft";
+
+/// The wasm the simulator installs & runs, `make tests-wasm` from `market` puts all three in
+/// `market/tests/wasm`.
+pub struct Wasm {
+    pub market: Vec<u8>,
+    pub payment: Vec<u8>,
+    pub cep47: Vec<u8>
+}
+
+impl Wasm {
+    pub fn load<P: AsRef<Path>>(directory: P) -> Result<Wasm, Error> {
+        let read = |name: &str| fs::read(directory.as_ref().join(name));
+        Ok(Wasm {
+            market: read(MARKET_WASM)?,
+            payment: read(PAYMENT_WASM)?,
+            cep47: read(CEP47_WASM)?
+        })
+    }
+}
+
+fn blake2b(bytes: &[u8]) -> [u8; 32] {
+    let mut result = [0; 32];
+    let mut hasher = VarBlake2b::new(32).unwrap();
+    hasher.update(bytes);
+    hasher.finalize_variable(|hash| result.copy_from_slice(hash));
+    result
+}
+
+// named actors get the same account in every run
+fn actor_account(name: &str) -> AccountHash {
+    AccountHash::new(blake2b(format!("market-simulator {}", name).as_bytes()))
+}
+
+// `get_key_id` in market-core
+fn key_id(key: &Key) -> String {
+    hex::encode(blake2b(&key.to_bytes().unwrap()))
+}
+
+fn describe_error(error: &EngineError) -> String {
+    match error {
+        EngineError::Exec(ExecError::Revert(ApiError::User(code))) => match MarketError::from_code(*code) {
+            Some(market_error) => format!("{:?} ({})", market_error, code),
+            None => format!("user error {}", code)
+        },
+        other => other.to_string()
+    }
+}
+
+pub struct StepOutcome {
+    pub step: Step,
+    /// Why the deploy reverted, if it did.
+    pub error: Option<String>,
+    pub gas: U512
+}
+
+/// Where a scenario ended up. Balances are main purse balances, so they include what each
+/// actor's deploys paid for gas.
+pub struct Report {
+    pub steps: Vec<StepOutcome>,
+    pub balances: Vec<(String, U512)>,
+    pub claimable: Vec<(String, U512)>,
+    pub owners: Vec<(String, Option<String>)>,
+    pub events: Vec<String>
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "steps")?;
+        for (index, outcome) in self.steps.iter().enumerate() {
+            let result = outcome.error.as_ref().map_or_else(|| "ok".to_string(), |error| format!("reverted: {}", error));
+            writeln!(f, "  {}. {}: {} (gas {})", index + 1, outcome.step, result, outcome.gas)?;
+        }
+        writeln!(f, "balances")?;
+        for ((name, balance), (_, claimable)) in self.balances.iter().zip(&self.claimable) {
+            writeln!(f, "  {}: {} motes, {} claimable", name, balance, claimable)?;
+        }
+        writeln!(f, "owners")?;
+        for (token_id, owner) in &self.owners {
+            writeln!(f, "  {}: {}", token_id, owner.as_deref().unwrap_or("none"))?;
+        }
+        writeln!(f, "events")?;
+        for (index, event) in self.events.iter().enumerate() {
+            writeln!(f, "  {}. {}", index, event)?;
+        }
+        Ok(())
+    }
+}
+
+/// CEP-47 & the market installed in a fresh in-memory engine, with the scenario's actors funded
+/// & its tokens minted.
+pub struct Simulator {
+    builder: InMemoryWasmTestBuilder,
+    scenario: Scenario,
+    payment_wasm: Vec<u8>,
+    admin: AccountHash,
+    actors: BTreeMap<String, AccountHash>,
+    market: ContractHash,
+    market_package: ContractPackageHash,
+    token: ContractHash
+}
+
+impl Simulator {
+    pub fn new(wasm: Wasm, scenario: Scenario) -> Result<Simulator, Error> {
+        let mut builder = InMemoryWasmTestBuilder::default();
+        builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+        let admin = *DEFAULT_ACCOUNT_ADDR;
+
+        let mut actors = BTreeMap::new();
+        for actor in &scenario.actors {
+            let account = actor_account(&actor.name);
+            let transfer = ExecuteRequestBuilder::transfer(admin, runtime_args! {
+                mint::ARG_AMOUNT => U512::from(actor.balance),
+                mint::ARG_TARGET => account,
+                mint::ARG_ID => Option::<u64>::None
+            }).build();
+            setup(&mut builder, transfer, &format!("funding {}", actor.name))?;
+            actors.insert(actor.name.clone(), account);
+        }
+
+        let install_token = ExecuteRequestBuilder::module_bytes(admin, wasm.cep47, runtime_args! {
+            "name" => "Simulator NFT".to_string(),
+            "symbol" => "SIM".to_string(),
+            "meta" => BTreeMap::<String, String>::new(),
+            "contract_name" => CEP47_CONTRACT_NAME.to_string()
+        }).build();
+        setup(&mut builder, install_token, "installing CEP-47")?;
+
+        let install_market = ExecuteRequestBuilder::module_bytes(admin, wasm.market, runtime_args! {
+            MAX_OFFERS_PER_TOKEN_ARG => scenario.max_offers_per_token,
+            MIN_OFFER_AMOUNT_ARG => U512::from(scenario.min_offer_amount)
+        }).build();
+        setup(&mut builder, install_market, "installing the market")?;
+
+        let admin_account = builder.get_expected_account(admin);
+        let named_hash = |name: &str| admin_account.named_keys()
+            .get(name)
+            .and_then(|key| key.into_hash())
+            .ok_or_else(|| Error::Setup(format!("installer has no {}", name)));
+
+        let mut simulator = Simulator {
+            market: ContractHash::new(named_hash("market_contract_hash")?),
+            market_package: ContractPackageHash::new(named_hash("market_contract_package_hash")?),
+            token: ContractHash::new(named_hash(&format!("{}_contract_hash", CEP47_CONTRACT_NAME))?),
+            builder,
+            scenario,
+            payment_wasm: wasm.payment,
+            admin,
+            actors
+        };
+
+        for mint in simulator.scenario.mints.clone() {
+            let mut meta = BTreeMap::new();
+            meta.insert("name".to_string(), format!("token {}", mint.token_id));
+            let args = runtime_args! {
+                "recipient" => Key::Account(simulator.actors[&mint.owner]),
+                "token_ids" => vec![parse_token_id(&mint.token_id)?],
+                "token_metas" => vec![meta]
+            };
+            let request = ExecuteRequestBuilder::contract_call_by_hash(simulator.admin, simulator.token, "mint", args).build();
+            simulator.exec(request)
+                .map_err(|error| Error::Setup(format!("minting {}: {}", mint.token_id, describe_error(&error))))?;
+        }
+        Ok(simulator)
+    }
+
+    /// Runs every step in order, a reverted step is reported & the rest still run.
+    pub fn run(mut self) -> Result<Report, Error> {
+        let mut steps = Vec::new();
+        for step in self.scenario.steps.clone() {
+            let error = self.step(&step)?.err().map(|error| describe_error(&error));
+            let gas = self.builder.last_exec_gas_cost().value();
+            steps.push(StepOutcome { step, error, gas });
+        }
+
+        let balances = self.scenario.actors.iter()
+            .map(|actor| (actor.name.clone(), self.balance(self.actors[&actor.name])))
+            .collect();
+        let claimable = self.scenario.actors.iter()
+            .map(|actor| (actor.name.clone(), self.claimable(&Key::Account(self.actors[&actor.name]))))
+            .collect();
+        let owners = self.scenario.mints.iter()
+            .map(|mint| (mint.token_id.clone(), self.owner_of(&mint.token_id).map(|owner| self.name_of(&owner))))
+            .collect();
+        let events = self.events()?
+            .iter()
+            .map(|event| self.describe_event(event))
+            .collect();
+
+        Ok(Report { steps, balances, claimable, owners, events })
+    }
+
+    // the outer error is the simulator's, the inner one the step's own revert
+    fn step(&mut self, step: &Step) -> Result<Result<(), EngineError>, Error> {
+        let actor = self.actors[step.actor()];
+        let token_arg = self.token.to_formatted_string();
+
+        let result = match step {
+            Step::List { token_id, price, .. } => {
+                let approve = runtime_args! {
+                    "spender" => Key::from(self.market_package),
+                    "token_ids" => vec![parse_token_id(token_id)?]
+                };
+                let request = ExecuteRequestBuilder::contract_call_by_hash(actor, self.token, "approve", approve).build();
+                self.exec(request).and_then(|_| self.call_market(actor, "create_listing", runtime_args! {
+                    NFT_CONTRACT_HASH_ARG => token_arg,
+                    TOKEN_ID_ARG => token_id.clone(),
+                    PRICE_ARG => U512::from(*price)
+                }))
+            },
+            Step::Cancel { token_id, .. } => self.call_market(actor, "cancel_listing", runtime_args! {
+                NFT_CONTRACT_HASH_ARG => token_arg,
+                TOKEN_ID_ARG => token_id.clone()
+            }),
+            Step::Buy { token_id, amount, .. } => self.pay_market(actor, "buy_listing", token_id, *amount),
+            Step::BuyFloor { amount, .. } => self.pay_market(actor, "buy_floor", "", *amount),
+            Step::Offer { token_id, amount, .. } => self.pay_market(actor, "make_offer", token_id, *amount),
+            Step::Withdraw { token_id, .. } => self.call_market(actor, "withdraw_offer", runtime_args! {
+                NFT_CONTRACT_HASH_ARG => token_arg,
+                TOKEN_ID_ARG => token_id.clone()
+            }),
+            Step::Accept { token_id, bidder, .. } => {
+                let bidder = Key::Account(self.actors[bidder]);
+                self.call_market(actor, "accept_offer", runtime_args! {
+                    NFT_CONTRACT_HASH_ARG => token_arg,
+                    TOKEN_ID_ARG => token_id.clone(),
+                    ACCEPTED_OFFER_ARG => bidder.to_formatted_string()
+                })
+            },
+            Step::Claim { .. } => self.call_market(actor, "claim", runtime_args! {
+                CLAIM_PURSE_ARG => Option::<URef>::None
+            })
+        };
+        Ok(result)
+    }
+
+    fn exec(&mut self, request: ExecuteRequest) -> Result<(), EngineError> {
+        self.builder.exec(request).commit();
+        match self.builder.get_error() {
+            Some(error) => Err(error),
+            None => Ok(())
+        }
+    }
+
+    fn call_market(&mut self, sender: AccountHash, entry_point: &str, args: RuntimeArgs) -> Result<(), EngineError> {
+        let request = ExecuteRequestBuilder::contract_call_by_hash(sender, self.market, entry_point, args).build();
+        self.exec(request)
+    }
+
+    // the way clients fund `buy_listing`, `make_offer` & `buy_floor`
+    fn pay_market(&mut self, sender: AccountHash, entry_point: &str, token_id: &str, amount: u64) -> Result<(), EngineError> {
+        let request = ExecuteRequestBuilder::module_bytes(sender, self.payment_wasm.clone(), runtime_args! {
+            MARKET_CONTRACT_HASH_ARG => self.market.to_formatted_string(),
+            MARKET_ENTRY_POINT_NAME_ARG => entry_point.to_string(),
+            NFT_CONTRACT_HASH_ARG => self.token.to_formatted_string(),
+            TOKEN_ID_ARG => token_id.to_string(),
+            AMOUNT_ARG => U512::from(amount)
+        }).build();
+        self.exec(request)
+    }
+
+    fn balance(&self, account: AccountHash) -> U512 {
+        let purse = self.builder.get_expected_account(account).main_purse();
+        self.builder.get_purse_balance(purse)
+    }
+
+    fn market_named_key(&self, name: &str) -> Option<Key> {
+        self.builder.get_contract(self.market)?
+            .named_keys()
+            .get(name)
+            .copied()
+    }
+
+    fn market_dictionary_item(&self, dictionary: &str, item_key: &str) -> Option<CLValue> {
+        let dictionary_uref = self.market_named_key(dictionary)?.into_uref()?;
+        self.builder.query_dictionary_item(None, dictionary_uref, item_key)
+            .ok()
+            .and_then(|stored| stored.as_cl_value().cloned())
+    }
+
+    fn claimable(&self, claimant: &Key) -> U512 {
+        self.market_dictionary_item("claims", &key_id(claimant))
+            .and_then(|value| value.into_t().ok())
+            .unwrap_or_default()
+    }
+
+    // reads the CEP-47 `owners` dictionary, `owner_of` can't return to a deploy
+    fn owner_of(&self, token_id: &str) -> Option<Key> {
+        let owners_uref = self.builder.get_contract(self.token)?
+            .named_keys()
+            .get("owners")
+            .and_then(|key| key.into_uref())?;
+        let value = self.builder.query_dictionary_item(None, owners_uref, token_id)
+            .ok()?
+            .as_cl_value()
+            .cloned()?;
+        value.clone().into_t::<Key>().ok()
+            .or_else(|| value.into_t::<Option<Key>>().ok().flatten())
+    }
+
+    fn events(&self) -> Result<Vec<MarketEvent>, Error> {
+        let length = self.market_named_key(EVENTS_LENGTH)
+            .and_then(|key| self.builder.query(None, key, &[]).ok())
+            .and_then(|stored| stored.as_cl_value().cloned())
+            .and_then(|value| value.into_t::<u32>().ok())
+            .ok_or_else(|| Error::Setup("market has no events length".to_string()))?;
+
+        (0..length)
+            .map(|index| {
+                let value = self.market_dictionary_item(EVENTS_DICT, &index.to_string())
+                    .ok_or_else(|| Error::Setup(format!("market event {} is missing", index)))?;
+                let event_bytes = value.into_t::<Bytes>()
+                    .map_err(|_| Error::Setup(format!("market event {} isn't stored as bytes", index)))?;
+                Ok(from_ces_bytes(&event_bytes)?)
+            })
+            .collect()
+    }
+
+    fn name_of(&self, key: &Key) -> String {
+        self.actors.iter()
+            .find(|(_, account)| Key::Account(**account) == *key)
+            .map_or_else(|| key.to_formatted_string(), |(name, _)| name.clone())
+    }
+
+    fn describe_event(&self, event: &MarketEvent) -> String {
+        match event {
+            MarketEvent::ListingCreated { seller, token_id, price, .. } =>
+                format!("ListingCreated: {} listed {} for {}", self.name_of(seller), token_id, price),
+            MarketEvent::ListingPurchased { seller, buyer, token_id, price, .. } =>
+                format!("ListingPurchased: {} bought {} from {} for {}", self.name_of(buyer), token_id, self.name_of(seller), price),
+            MarketEvent::ListingCanceled { token_id, .. } =>
+                format!("ListingCanceled: {}", token_id),
+            MarketEvent::OfferCreated { buyer, token_id, price, .. } =>
+                format!("OfferCreated: {} offered {} for {}", self.name_of(buyer), price, token_id),
+            MarketEvent::OfferWithdraw { buyer, token_id, .. } =>
+                format!("OfferWithdraw: {} withdrew their offer for {}", self.name_of(buyer), token_id),
+            MarketEvent::OfferAccepted { seller, buyer, token_id, price, .. } =>
+                format!("OfferAccepted: {} sold {} to {} for {}", self.name_of(seller), token_id, self.name_of(buyer), price),
+        }
+    }
+}
+
+fn setup(builder: &mut InMemoryWasmTestBuilder, request: ExecuteRequest, what: &str) -> Result<(), Error> {
+    builder.exec(request).commit();
+    match builder.get_error() {
+        Some(error) => Err(Error::Setup(format!("{}: {}", what, describe_error(&error)))),
+        None => Ok(())
+    }
+}
+
+fn parse_token_id(token_id: &str) -> Result<U256, Error> {
+    U256::from_dec_str(token_id)
+        .map_err(|_| Error::Scenario(format!("token id {} isn't a number", token_id)))
+}
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use market_simulator::{Error, Scenario, Step};
+
+fn scenario_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("scenarios").join(name)
+}
+
+fn invalid(result: Result<Scenario, Error>) -> String {
+    match result {
+        Err(Error::Scenario(message)) => message,
+        other => panic!("expected an invalid scenario, got {:?}", other)
+    }
+}
+
+#[test]
+fn should_load_the_example_scenarios() {
+    let offers = Scenario::load(scenario_path("offer_accepted.yaml")).expect("yaml scenario should load");
+    assert_eq!(offers.actors.len(), 3);
+    assert_eq!(offers.actors[2].balance, 500_000_000_000);
+    assert_eq!(offers.steps[3], Step::Accept {
+        actor: "alice".to_string(),
+        token_id: "1".to_string(),
+        bidder: "carol".to_string()
+    });
+
+    let floor = Scenario::load(scenario_path("floor.json")).expect("json scenario should load");
+    assert_eq!(floor.steps[2], Step::BuyFloor { actor: "bob".to_string(), amount: 250_000_000_000 });
+}
+
+#[test]
+fn should_fill_in_defaults() {
+    let scenario = Scenario::from_json(r#"{ "actors": [{ "name": "alice" }] }"#).unwrap();
+
+    assert_eq!(scenario.max_offers_per_token, 3);
+    assert_eq!(scenario.min_offer_amount, 1_000_000_000);
+    assert_eq!(scenario.actors[0].balance, 100_000_000_000_000);
+    assert!(scenario.mints.is_empty() && scenario.steps.is_empty());
+}
+
+#[test]
+fn should_reject_unknown_actors() {
+    let unknown_owner = r#"{ "actors": [{ "name": "alice" }], "mints": [{ "owner": "bob", "token_id": "1" }] }"#;
+    assert_eq!(invalid(Scenario::from_json(unknown_owner)), "unknown actor bob");
+
+    let unknown_bidder = r#"{
+        "actors": [{ "name": "alice" }],
+        "steps": [{ "action": "accept", "actor": "alice", "token_id": "1", "bidder": "carol" }]
+    }"#;
+    assert_eq!(invalid(Scenario::from_json(unknown_bidder)), "unknown actor carol");
+
+    let twice = r#"{ "actors": [{ "name": "alice" }, { "name": "alice" }] }"#;
+    assert_eq!(invalid(Scenario::from_json(twice)), "actor alice is declared twice");
+    assert_eq!(invalid(Scenario::from_json(r#"{ "actors": [] }"#)), "no actors");
+}
+
+#[test]
+fn should_reject_unknown_actions_and_fields() {
+    let unknown_action = "actors: [{ name: alice }]\nsteps: [{ action: burn, actor: alice }]";
+    assert!(matches!(Scenario::from_yaml(unknown_action), Err(Error::Yaml(_))));
+
+    let typo = r#"{ "actors": [{ "name": "alice", "balanse": 1 }] }"#;
+    assert!(matches!(Scenario::from_json(typo), Err(Error::Json(_))));
+}
+
+#[test]
+fn should_describe_steps() {
+    let step = Step::Offer { actor: "bob".to_string(), token_id: "7".to_string(), amount: 5 };
+    assert_eq!(step.to_string(), "bob offers 5 for 7");
+}
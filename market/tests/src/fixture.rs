@@ -0,0 +1,454 @@
+//! Shared setup for the market integration tests: genesis, a funded account per role, a CEP-47
+//! collection & the market installed in the in-memory engine, with helpers to call them & read
+//! their state back.
+//!
+//! `make test` copies the market & payment wasm into `tests/wasm`, along with the `cep47-token.wasm`
+//! built from https://github.com/casper-ecosystem/casper-nft-cep47 (see `CEP47_WASM` in the Makefile).
+
+use std::collections::BTreeMap;
+
+use blake2::{
+    digest::{Update, VariableOutput},
+    VarBlake2b
+};
+use casper_engine_test_support::{
+    ExecuteRequestBuilder, InMemoryWasmTestBuilder, DEFAULT_ACCOUNT_ADDR, DEFAULT_RUN_GENESIS_REQUEST
+};
+use casper_execution_engine::core::{
+    engine_state::{Error as EngineError, ExecuteRequest},
+    execution::Error as ExecError
+};
+use casper_types::{
+    account::AccountHash,
+    bytesrepr::{FromBytes, ToBytes},
+    runtime_args, system::mint, ApiError, CLValue, ContractHash, ContractPackageHash, Key,
+    RuntimeArgs, URef, URefAddr, U256, U512
+};
+
+pub const MARKET_WASM: &str = "market.wasm";
+pub const PAYMENT_WASM: &str = "payment.wasm";
+pub const CEP47_WASM: &str = "cep47-token.wasm";
+pub const MOCK_TOKEN_WASM: &str = "mock-token.wasm";
+
+const CEP47_CONTRACT_NAME: &str = "market_test_nft";
+const MOCK_TOKEN_CONTRACT_NAME: &str = "mock_token";
+
+pub const MAX_OFFERS_PER_TOKEN: u32 = 3;
+pub const MIN_OFFER_AMOUNT: u64 = 1_000_000_000;
+
+// 100k CSPR per test account
+const ACCOUNT_FUNDING: u64 = 100_000_000_000_000;
+
+pub fn cspr(amount: u64) -> U512 {
+    U512::from(amount) * U512::from(1_000_000_000u64)
+}
+
+// the market's own error codes, not every error can be reached from a deploy
+pub use market_core::Error as MarketError;
+
+pub fn assert_market_error(result: Result<(), EngineError>, expected: MarketError) {
+    match result {
+        Err(EngineError::Exec(ExecError::Revert(ApiError::User(code)))) => assert_eq!(
+            code, expected as u16,
+            "expected {:?} ({}), got user error {}", expected, expected as u16, code
+        ),
+        other => panic!("expected {:?}, got {:?}", expected, other)
+    }
+}
+
+/// For reverts that don't come from the market, e.g. the mock token's or the engine's.
+pub fn assert_api_error(result: Result<(), EngineError>, expected: ApiError) {
+    match result {
+        Err(EngineError::Exec(ExecError::Revert(error))) => assert_eq!(error, expected),
+        other => panic!("expected {:?}, got {:?}", expected, other)
+    }
+}
+
+// same hashing as `get_id` & `get_key_id` in the contract
+fn blake2b_hex(bytes: &[u8]) -> String {
+    let mut result = [0; 32];
+    let mut hasher = VarBlake2b::new(32).unwrap();
+    hasher.update(bytes);
+    hasher.finalize_variable(|hash| result.copy_from_slice(hash));
+    hex::encode(result)
+}
+
+fn get_id(token_contract: &str, token_id: &str) -> String {
+    let mut bytes = token_contract.to_string().to_bytes().unwrap();
+    bytes.append(&mut token_id.to_string().to_bytes().unwrap());
+    blake2b_hex(&bytes)
+}
+
+fn get_key_id(key: &Key) -> String {
+    blake2b_hex(&key.to_bytes().unwrap())
+}
+
+/// What the mock token's `transfer_from` does, see `market/mock-token`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockTransfer {
+    Honest,
+    Revert,
+    // returns without moving the token
+    Ignore,
+    // calls the named market entry point as the mock, then transfers honestly
+    Reenter(&'static str)
+}
+
+impl Default for MockTransfer {
+    fn default() -> Self {
+        MockTransfer::Honest
+    }
+}
+
+// `ApiError::User` code the mock reverts `transfer_from` with in `MockTransfer::Revert`
+pub const MOCK_TRANSFER_REFUSED: u16 = 1;
+
+/// The mock token's misbehavior, the default is an honest token.
+#[derive(Debug, Clone, Default)]
+pub struct MockBehavior {
+    // `owner_of` returns this for every token
+    pub owner_override: Option<Key>,
+    // `get_approved` returns this for every token
+    pub approved_override: Option<Key>,
+    pub transfer: MockTransfer,
+    // the `accepted_offer` arg a reentrant `transfer_from` passes the market
+    pub reenter_offer: Option<AccountHash>
+}
+
+fn fund(builder: &mut InMemoryWasmTestBuilder, admin: AccountHash, account: AccountHash) {
+    let transfer = ExecuteRequestBuilder::transfer(admin, runtime_args! {
+        mint::ARG_AMOUNT => U512::from(ACCOUNT_FUNDING),
+        mint::ARG_TARGET => account,
+        mint::ARG_ID => Option::<u64>::None
+    }).build();
+    builder.exec(transfer).expect_success().commit();
+}
+
+pub struct MarketFixture {
+    pub builder: InMemoryWasmTestBuilder,
+    pub market: ContractHash,
+    pub market_package: ContractPackageHash,
+    pub token: ContractHash,
+    pub admin: AccountHash,
+    pub seller: AccountHash,
+    pub buyer: AccountHash,
+    pub bidder: AccountHash
+}
+
+impl MarketFixture {
+    pub fn new() -> Self {
+        MarketFixture::with_config(MAX_OFFERS_PER_TOKEN, MIN_OFFER_AMOUNT)
+    }
+
+    /// A market installed with its own `max_offers_per_token` & `min_offer_amount`.
+    pub fn with_config(max_offers_per_token: u32, min_offer_amount: u64) -> Self {
+        let mut builder = InMemoryWasmTestBuilder::default();
+        builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+        let admin = *DEFAULT_ACCOUNT_ADDR;
+        let seller = AccountHash::new([1; 32]);
+        let buyer = AccountHash::new([2; 32]);
+        let bidder = AccountHash::new([3; 32]);
+
+        for account in [seller, buyer, bidder] {
+            fund(&mut builder, admin, account);
+        }
+
+        let mut meta = BTreeMap::new();
+        meta.insert("origin".to_string(), "market tests".to_string());
+        let install_token = ExecuteRequestBuilder::standard(admin, CEP47_WASM, runtime_args! {
+            "name" => "Market Test NFT".to_string(),
+            "symbol" => "MTN".to_string(),
+            "meta" => meta,
+            "contract_name" => CEP47_CONTRACT_NAME.to_string()
+        }).build();
+        builder.exec(install_token).expect_success().commit();
+
+        let install_market = ExecuteRequestBuilder::standard(admin, MARKET_WASM, runtime_args! {
+            "max_offers_per_token" => max_offers_per_token,
+            "min_offer_amount" => U512::from(min_offer_amount)
+        }).build();
+        builder.exec(install_market).expect_success().commit();
+
+        let admin_account = builder.get_expected_account(admin);
+        let named_hash = |name: &str| admin_account.named_keys()
+            .get(name)
+            .and_then(|key| key.into_hash())
+            .unwrap_or_else(|| panic!("installer should have {}", name));
+
+        MarketFixture {
+            market: ContractHash::new(named_hash("market_contract_hash")),
+            market_package: ContractPackageHash::new(named_hash("market_contract_package_hash")),
+            token: ContractHash::new(named_hash(&format!("{}_contract_hash", CEP47_CONTRACT_NAME))),
+            builder,
+            admin,
+            seller,
+            buyer,
+            bidder
+        }
+    }
+
+    /// A market whose `token` is the configurable mock token instead of a CEP-47 collection,
+    /// every token helper below works against it unchanged.
+    pub fn with_mock_token() -> Self {
+        let mut fixture = MarketFixture::new();
+        let install_mock = ExecuteRequestBuilder::standard(fixture.admin, MOCK_TOKEN_WASM, RuntimeArgs::new()).build();
+        fixture.builder.exec(install_mock).expect_success().commit();
+
+        let mock_hash = fixture.builder.get_expected_account(fixture.admin)
+            .named_keys()
+            .get(&format!("{}_contract_hash", MOCK_TOKEN_CONTRACT_NAME))
+            .and_then(|key| key.into_hash())
+            .expect("installer should have the mock token");
+        fixture.token = ContractHash::new(mock_hash);
+        fixture
+    }
+
+    pub fn configure_mock(&mut self, behavior: MockBehavior) {
+        let (transfer_mode, reenter_entry_point) = match behavior.transfer {
+            MockTransfer::Honest => (0u8, ""),
+            MockTransfer::Revert => (1, ""),
+            MockTransfer::Ignore => (2, ""),
+            MockTransfer::Reenter(entry_point) => (3, entry_point)
+        };
+        let reenter_offer = behavior.reenter_offer
+            .map(|bidder| Key::Account(bidder).to_formatted_string())
+            .unwrap_or_default();
+
+        let market = self.market;
+        self.call_token(self.admin, "configure", runtime_args! {
+            "owner_override" => behavior.owner_override,
+            "approved_override" => behavior.approved_override,
+            "transfer_mode" => transfer_mode,
+            "reenter_market" => Some(market),
+            "reenter_entry_point" => reenter_entry_point.to_string(),
+            "reenter_offer" => reenter_offer
+        }).expect("configure should succeed");
+    }
+
+    /// Creates & funds another account, e.g. one more bidder.
+    pub fn new_account(&mut self, seed: u8) -> AccountHash {
+        let account = AccountHash::new([seed; 32]);
+        fund(&mut self.builder, self.admin, account);
+        account
+    }
+
+    /// Gas the last deploy consumed, session & every contract it called included.
+    pub fn last_gas(&self) -> U512 {
+        self.builder.last_exec_gas_cost().value()
+    }
+
+    pub fn exec(&mut self, request: ExecuteRequest) -> Result<(), EngineError> {
+        self.builder.exec(request).commit();
+        match self.builder.get_error() {
+            Some(error) => Err(error),
+            None => Ok(())
+        }
+    }
+
+    /// The `token_contract_hash` arg every market entry point takes.
+    pub fn token_arg(&self) -> String {
+        self.token.to_formatted_string()
+    }
+
+    pub fn call_market(&mut self, sender: AccountHash, entry_point: &str, args: RuntimeArgs) -> Result<(), EngineError> {
+        let request = ExecuteRequestBuilder::contract_call_by_hash(sender, self.market, entry_point, args).build();
+        self.exec(request)
+    }
+
+    pub fn call_token(&mut self, sender: AccountHash, entry_point: &str, args: RuntimeArgs) -> Result<(), EngineError> {
+        let request = ExecuteRequestBuilder::contract_call_by_hash(sender, self.token, entry_point, args).build();
+        self.exec(request)
+    }
+
+    /// Runs the payment wasm as session code, the way clients fund `buy_listing`, `make_offer`
+    /// & `buy_floor`.
+    pub fn pay_market(&mut self, sender: AccountHash, entry_point: &str, token_id: &str, amount: U512) -> Result<(), EngineError> {
+        let request = ExecuteRequestBuilder::standard(sender, PAYMENT_WASM, runtime_args! {
+            "market_contract_hash" => self.market.to_formatted_string(),
+            "entry_point_name" => entry_point.to_string(),
+            "token_contract_hash" => self.token_arg(),
+            "token_id" => token_id.to_string(),
+            "amount" => amount
+        }).build();
+        self.exec(request)
+    }
+
+    pub fn mint(&mut self, owner: AccountHash, token_id: &str) {
+        let mut meta = BTreeMap::new();
+        meta.insert("name".to_string(), format!("token {}", token_id));
+        self.call_token(self.admin, "mint", runtime_args! {
+            "recipient" => Key::Account(owner),
+            "token_ids" => vec![U256::from_dec_str(token_id).unwrap()],
+            "token_metas" => vec![meta]
+        }).expect("mint should succeed");
+    }
+
+    /// CEP-47 approvals name the spender, which for the market is its package hash.
+    pub fn approve(&mut self, owner: AccountHash, spender: Key, token_id: &str) {
+        self.call_token(owner, "approve", runtime_args! {
+            "spender" => spender,
+            "token_ids" => vec![U256::from_dec_str(token_id).unwrap()]
+        }).expect("approve should succeed");
+    }
+
+    pub fn approve_market(&mut self, owner: AccountHash, token_id: &str) {
+        self.approve(owner, Key::from(self.market_package), token_id);
+    }
+
+    /// Mints to the seller, approves the market & lists.
+    pub fn listed_token(&mut self, token_id: &str, price: U512) {
+        let seller = self.seller;
+        self.mint(seller, token_id);
+        self.approve_market(seller, token_id);
+        self.list(seller, token_id, price).expect("listing should succeed");
+    }
+
+    pub fn list(&mut self, seller: AccountHash, token_id: &str, price: U512) -> Result<(), EngineError> {
+        self.call_market(seller, "create_listing", runtime_args! {
+            "token_contract_hash" => self.token_arg(),
+            "token_id" => token_id.to_string(),
+            "price" => price
+        })
+    }
+
+    /// Lists with the token moved to the market, the market has to be approved for it first.
+    pub fn list_in_custody(&mut self, seller: AccountHash, token_id: &str, price: U512) -> Result<(), EngineError> {
+        self.call_market(seller, "create_custody_listing", runtime_args! {
+            "token_contract_hash" => self.token_arg(),
+            "token_id" => token_id.to_string(),
+            "price" => price
+        })
+    }
+
+    pub fn cancel(&mut self, seller: AccountHash, token_id: &str) -> Result<(), EngineError> {
+        self.call_market(seller, "cancel_listing", runtime_args! {
+            "token_contract_hash" => self.token_arg(),
+            "token_id" => token_id.to_string()
+        })
+    }
+
+    pub fn buy(&mut self, buyer: AccountHash, token_id: &str, amount: U512) -> Result<(), EngineError> {
+        self.pay_market(buyer, "buy_listing", token_id, amount)
+    }
+
+    pub fn offer(&mut self, bidder: AccountHash, token_id: &str, amount: U512) -> Result<(), EngineError> {
+        self.pay_market(bidder, "make_offer", token_id, amount)
+    }
+
+    pub fn withdraw(&mut self, bidder: AccountHash, token_id: &str) -> Result<(), EngineError> {
+        self.call_market(bidder, "withdraw_offer", runtime_args! {
+            "token_contract_hash" => self.token_arg(),
+            "token_id" => token_id.to_string()
+        })
+    }
+
+    pub fn accept(&mut self, seller: AccountHash, token_id: &str, bidder: AccountHash) -> Result<(), EngineError> {
+        self.call_market(seller, "accept_offer", runtime_args! {
+            "token_contract_hash" => self.token_arg(),
+            "token_id" => token_id.to_string(),
+            "accepted_offer" => Key::Account(bidder).to_formatted_string()
+        })
+    }
+
+    pub fn claim(&mut self, claimant: AccountHash) -> Result<(), EngineError> {
+        self.call_market(claimant, "claim", runtime_args! {
+            "purse" => Option::<URef>::None
+        })
+    }
+
+    pub fn offer_bidders(&self, token_id: &str) -> Vec<Key> {
+        self.dictionary_item("offer_index", &get_id(&self.token_arg(), token_id))
+            .map_or_else(Vec::new, |value| value.into_t().expect("offer_index should hold Vec<Key>"))
+    }
+
+    pub fn balance(&self, account: AccountHash) -> U512 {
+        let purse = self.builder.get_expected_account(account).main_purse();
+        self.builder.get_purse_balance(purse)
+    }
+
+    fn market_named_key(&self, name: &str) -> Option<Key> {
+        self.builder.get_contract(self.market)
+            .expect("market should be installed")
+            .named_keys()
+            .get(name)
+            .copied()
+    }
+
+    /// Balance of `offers_purse` or `claims_purse`, zero until the market first creates it.
+    pub fn market_purse_balance(&self, name: &str) -> U512 {
+        self.market_named_key(name)
+            .and_then(|key| key.into_uref())
+            .map_or_else(U512::zero, |purse| self.builder.get_purse_balance(purse))
+    }
+
+    fn dictionary_item(&self, dictionary: &str, item_key: &str) -> Option<CLValue> {
+        let dictionary_uref = self.market_named_key(dictionary)?.into_uref()?;
+        self.builder.query_dictionary_item(None, dictionary_uref, item_key)
+            .ok()
+            .and_then(|stored| stored.as_cl_value().cloned())
+    }
+
+    /// The active listing's seller & price, canceled & sold listings are stored as `None`.
+    pub fn listing(&self, token_id: &str) -> Option<(Key, U512)> {
+        let value = self.dictionary_item("listings", &get_id(&self.token_arg(), token_id))?;
+        let (seller, remainder) = Key::from_bytes(value.inner_bytes()).ok()?;
+        let (_token_contract, remainder) = ContractHash::from_bytes(remainder).ok()?;
+        let (_token_id, remainder) = String::from_bytes(remainder).ok()?;
+        let (price, _) = U512::from_bytes(remainder).ok()?;
+        Some((seller, price))
+    }
+
+    pub fn in_custody(&self, token_id: &str) -> bool {
+        self.dictionary_item("custody_listings", &get_id(&self.token_arg(), token_id))
+            .map_or(false, |value| value.into_t().expect("custody should hold bool"))
+    }
+
+    pub fn offer_amount(&self, token_id: &str, bidder: AccountHash) -> Option<U512> {
+        let offers_id = get_id(&self.token_arg(), token_id);
+        let offer_id = get_id(&offers_id, &get_key_id(&Key::Account(bidder)));
+        self.dictionary_item("offers", &offer_id)?
+            .into_t::<Option<U512>>()
+            .expect("offers should hold Option<U512>")
+    }
+
+    pub fn claimable(&self, claimant: AccountHash) -> U512 {
+        self.dictionary_item("claims", &get_key_id(&Key::Account(claimant)))
+            .map_or_else(U512::zero, |value| value.into_t().expect("claims should hold U512"))
+    }
+
+    pub fn events_length(&self) -> u32 {
+        let length_uref = self.market_named_key("__events_length")
+            .expect("events should be initialized at install");
+        self.builder.query(None, length_uref, &[])
+            .expect("events length should be readable")
+            .as_cl_value()
+            .cloned()
+            .unwrap()
+            .into_t()
+            .unwrap()
+    }
+
+    /// Seed address of the market's `__events` dictionary, which indexers pick its writes out by.
+    pub fn events_dictionary(&self) -> URefAddr {
+        self.market_named_key("__events")
+            .and_then(|key| key.into_uref())
+            .expect("events should be initialized at install")
+            .addr()
+    }
+
+    /// Reads the CEP-47 `owners` dictionary directly, `owner_of` can't return to a deploy.
+    pub fn owner_of(&self, token_id: &str) -> Option<Key> {
+        let owners_uref = self.builder.get_contract(self.token)
+            .expect("token should be installed")
+            .named_keys()
+            .get("owners")
+            .and_then(|key| key.into_uref())?;
+        let value = self.builder.query_dictionary_item(None, owners_uref, token_id)
+            .ok()?
+            .as_cl_value()
+            .cloned()?;
+        value.clone().into_t::<Key>().ok()
+            .or_else(|| value.into_t::<Option<Key>>().ok().flatten())
+    }
+}
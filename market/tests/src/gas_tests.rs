@@ -0,0 +1,166 @@
+//! Gas each market entry point consumes as a collection's listings & a token's offers grow,
+//! checked against `gas_baseline.txt`. A call fails the suite when it costs more than the
+//! baseline plus the file's `threshold_percent`.
+//!
+//! After an intended cost change, rewrite the baseline with `make gas-baseline` from `market`
+//! & commit it with the change.
+
+use std::{collections::BTreeMap, env, fs};
+
+use casper_types::{account::AccountHash, U512};
+
+use crate::fixture::{cspr, MarketFixture, MIN_OFFER_AMOUNT};
+
+const BASELINE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/gas_baseline.txt");
+// set to `record` to rewrite the baseline instead of checking against it
+const RECORD_VAR: &str = "GAS_BASELINE";
+
+// how many other listings or offers are open when each call is measured
+const SCALES: [u32; 3] = [0, 8, 32];
+// every offer at the largest scale plus the measured one has to fit
+const MAX_OFFERS_PER_TOKEN: u32 = 64;
+
+// the measured listing is the cheapest so `buy_floor` picks it
+const TARGET_PRICE: u64 = 100;
+
+// first seed for the extra bidder accounts, clear of the fixture's own
+const BIDDER_SEED: u8 = 10;
+
+type Measurements = BTreeMap<(String, u32), U512>;
+
+struct Baseline {
+    threshold_percent: u64,
+    gas: Measurements
+}
+
+fn read_baseline() -> Baseline {
+    let contents = fs::read_to_string(BASELINE_PATH)
+        .unwrap_or_else(|error| panic!("couldn't read {}: {}", BASELINE_PATH, error));
+    let mut baseline = Baseline { threshold_percent: 0, gas: BTreeMap::new() };
+
+    for line in contents.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields.as_slice() {
+            ["threshold_percent", percent] => {
+                baseline.threshold_percent = percent.parse().expect("threshold_percent should be a number");
+            },
+            [entry_point, scale, gas] => {
+                let scale = scale.parse().expect("scale should be a number");
+                let gas = U512::from_dec_str(gas).expect("gas should be a number");
+                baseline.gas.insert((entry_point.to_string(), scale), gas);
+            },
+            _ => panic!("unexpected baseline line: {}", line)
+        }
+    }
+    baseline
+}
+
+fn write_baseline(threshold_percent: u64, measurements: &Measurements) {
+    let mut contents = String::from(
+        "# gas per market entry point, by how many other listings or offers were open\n\
+         # rewrite with `make gas-baseline` from `market`, see market/tests/src/gas_tests.rs\n"
+    );
+    contents.push_str(&format!("threshold_percent {}\n", threshold_percent));
+    for ((entry_point, scale), gas) in measurements {
+        contents.push_str(&format!("{} {} {}\n", entry_point, scale, gas));
+    }
+    fs::write(BASELINE_PATH, contents).expect("baseline should be writable");
+}
+
+fn record(measurements: &mut Measurements, entry_point: &str, scale: u32, fixture: &MarketFixture) {
+    measurements.insert((entry_point.to_string(), scale), fixture.last_gas());
+}
+
+// `background` listings from the seller, all dearer than the measured one
+fn measure_listings(background: u32, measurements: &mut Measurements) {
+    let mut fixture = MarketFixture::new();
+    let (seller, buyer, bidder) = (fixture.seller, fixture.buyer, fixture.bidder);
+
+    for token in 1..=background {
+        fixture.listed_token(&token.to_string(), cspr(TARGET_PRICE + u64::from(token)));
+    }
+    let target = (background + 1).to_string();
+    fixture.mint(seller, &target);
+    fixture.approve_market(seller, &target);
+
+    fixture.list(seller, &target, cspr(TARGET_PRICE)).expect("create_listing should succeed");
+    record(measurements, "create_listing", background, &fixture);
+
+    fixture.cancel(seller, &target).expect("cancel_listing should succeed");
+    record(measurements, "cancel_listing", background, &fixture);
+
+    fixture.list(seller, &target, cspr(TARGET_PRICE)).expect("listing should succeed");
+    fixture.buy(buyer, &target, cspr(TARGET_PRICE)).expect("buy_listing should succeed");
+    record(measurements, "buy_listing", background, &fixture);
+
+    fixture.approve_market(buyer, &target);
+    fixture.list(buyer, &target, cspr(TARGET_PRICE)).expect("listing should succeed");
+    fixture.pay_market(bidder, "buy_floor", "", cspr(TARGET_PRICE)).expect("buy_floor should succeed");
+    record(measurements, "buy_floor", background, &fixture);
+}
+
+// `background` offers from other bidders on the token the measured offer is made on
+fn measure_offers(background: u32, measurements: &mut Measurements) {
+    let mut fixture = MarketFixture::with_config(MAX_OFFERS_PER_TOKEN, MIN_OFFER_AMOUNT);
+    let (seller, bidder) = (fixture.seller, fixture.bidder);
+    let amount = U512::from(MIN_OFFER_AMOUNT);
+
+    fixture.mint(seller, "1");
+    fixture.approve_market(seller, "1");
+    let others: Vec<AccountHash> = (0..background)
+        .map(|index| fixture.new_account(BIDDER_SEED + index as u8))
+        .collect();
+    for other in &others {
+        fixture.offer(*other, "1", amount).expect("offer should succeed");
+    }
+
+    fixture.offer(bidder, "1", amount).expect("make_offer should succeed");
+    record(measurements, "make_offer", background, &fixture);
+
+    fixture.withdraw(bidder, "1").expect("withdraw_offer should succeed");
+    record(measurements, "withdraw_offer", background, &fixture);
+
+    // the other bids stay open, so this shouldn't grow with them
+    fixture.offer(bidder, "1", amount).expect("offer should succeed");
+    fixture.accept(seller, "1", bidder).expect("accept_offer should succeed");
+    record(measurements, "accept_offer", background, &fixture);
+
+    fixture.claim(seller).expect("claim should succeed");
+    record(measurements, "claim", background, &fixture);
+}
+
+#[test]
+fn should_stay_within_the_gas_baseline() {
+    let mut measurements = BTreeMap::new();
+    for background in SCALES {
+        measure_listings(background, &mut measurements);
+        measure_offers(background, &mut measurements);
+    }
+
+    let baseline = read_baseline();
+    if env::var(RECORD_VAR).map_or(false, |value| value == "record") {
+        write_baseline(baseline.threshold_percent, &measurements);
+        return;
+    }
+
+    let mut failures = Vec::new();
+    for ((entry_point, scale), gas) in &measurements {
+        let limit = baseline.gas.get(&(entry_point.clone(), *scale))
+            .map(|expected| *expected * U512::from(100 + baseline.threshold_percent) / U512::from(100));
+        match limit {
+            Some(limit) if *gas > limit => failures.push(format!(
+                "{} with {} open: {} gas, over the limit of {}", entry_point, scale, gas, limit
+            )),
+            Some(_) => (),
+            None => failures.push(format!(
+                "{} with {} open: {} gas, not in the baseline", entry_point, scale, gas
+            ))
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "gas regressed beyond {}% of {}, rerun `make gas-baseline` if that's intended:\n{}",
+        baseline.threshold_percent, BASELINE_PATH, failures.join("\n")
+    );
+}
@@ -0,0 +1,191 @@
+//! The market against the mock token in `market/mock-token`, which lies about ownership, hands
+//! back odd approval keys, refuses or skips transfers & calls back into the market. A hostile
+//! collection can always cheat its own buyers by lying consistently, these check it can't get
+//! anyone paid for a token that didn't move or touch funds the market holds.
+
+use casper_types::{AccessRights, ApiError, Key, URef};
+
+use crate::fixture::{
+    assert_api_error, assert_market_error, cspr, MarketError, MarketFixture, MockBehavior,
+    MockTransfer, MOCK_TRANSFER_REFUSED
+};
+
+fn offered_token(fixture: &mut MarketFixture, token_id: &str) {
+    let (seller, bidder) = (fixture.seller, fixture.bidder);
+    fixture.mint(seller, token_id);
+    fixture.offer(bidder, token_id, cspr(5)).expect("offer should succeed");
+}
+
+#[test]
+fn should_trade_through_an_honest_mock_token() {
+    let mut fixture = MarketFixture::with_mock_token();
+    let (seller, buyer, bidder) = (fixture.seller, fixture.buyer, fixture.bidder);
+
+    fixture.listed_token("1", cspr(100));
+    let seller_balance = fixture.balance(seller);
+    fixture.buy(buyer, "1", cspr(100)).expect("buy should succeed");
+    assert_eq!(fixture.owner_of("1"), Some(Key::Account(buyer)));
+    assert_eq!(fixture.balance(seller), seller_balance + cspr(100));
+
+    offered_token(&mut fixture, "2");
+    fixture.accept(seller, "2", bidder).expect("accept should succeed");
+    assert_eq!(fixture.owner_of("2"), Some(Key::Account(bidder)));
+    assert_eq!(fixture.claimable(seller), cspr(5));
+}
+
+#[test]
+fn should_only_take_the_market_package_as_approval() {
+    let mut fixture = MarketFixture::with_mock_token();
+    let seller = fixture.seller;
+    fixture.mint(seller, "1");
+
+    let market_contract = Key::Hash(fixture.market.value());
+    for (approved, expected) in [
+        (Key::Account(seller), MarketError::MissingApprovalResult),
+        (Key::URef(URef::new([7; 32], AccessRights::READ_ADD_WRITE)), MarketError::MissingApprovalResult),
+        // the contract hash isn't the identity the market calls with
+        (market_contract, MarketError::NeedsTransferApproval),
+        (Key::Hash([9; 32]), MarketError::NeedsTransferApproval)
+    ] {
+        fixture.configure_mock(MockBehavior { approved_override: Some(approved), ..Default::default() });
+        assert_market_error(fixture.list(seller, "1", cspr(100)), expected);
+    }
+    assert_eq!(fixture.listing("1"), None);
+}
+
+#[test]
+fn should_not_list_for_a_seller_the_token_disowns() {
+    let mut fixture = MarketFixture::with_mock_token();
+    let (seller, buyer) = (fixture.seller, fixture.buyer);
+    fixture.mint(seller, "1");
+    fixture.approve_market(seller, "1");
+
+    fixture.configure_mock(MockBehavior { owner_override: Some(Key::Account(buyer)), ..Default::default() });
+    assert_market_error(fixture.list(seller, "1", cspr(100)), MarketError::PermissionDenied);
+    assert_eq!(fixture.listing("1"), None);
+}
+
+#[test]
+fn should_not_pay_a_seller_the_token_lied_about() {
+    let mut fixture = MarketFixture::with_mock_token();
+    let (seller, buyer, bidder) = (fixture.seller, fixture.buyer, fixture.bidder);
+    fixture.mint(bidder, "1");
+    fixture.approve_market(bidder, "1");
+
+    // the listing goes through on the lie, the sale can't
+    fixture.configure_mock(MockBehavior { owner_override: Some(Key::Account(seller)), ..Default::default() });
+    fixture.list(seller, "1", cspr(100)).expect("listing should succeed");
+
+    let seller_balance = fixture.balance(seller);
+    assert_api_error(fixture.buy(buyer, "1", cspr(100)), ApiError::PermissionDenied);
+    assert_eq!(fixture.balance(seller), seller_balance);
+    assert_eq!(fixture.owner_of("1"), Some(Key::Account(bidder)));
+}
+
+#[test]
+fn should_not_pay_out_when_a_purchase_transfer_fails() {
+    let mut fixture = MarketFixture::with_mock_token();
+    let (seller, buyer) = (fixture.seller, fixture.buyer);
+    fixture.listed_token("1", cspr(100));
+    let seller_balance = fixture.balance(seller);
+
+    fixture.configure_mock(MockBehavior { transfer: MockTransfer::Revert, ..Default::default() });
+    assert_api_error(fixture.buy(buyer, "1", cspr(100)), ApiError::User(MOCK_TRANSFER_REFUSED));
+
+    fixture.configure_mock(MockBehavior { transfer: MockTransfer::Ignore, ..Default::default() });
+    assert_market_error(fixture.buy(buyer, "1", cspr(100)), MarketError::TokenNotTransferred);
+
+    assert_eq!(fixture.balance(seller), seller_balance);
+    assert_eq!(fixture.owner_of("1"), Some(Key::Account(seller)));
+    assert_eq!(fixture.listing("1"), Some((Key::Account(seller), cspr(100))));
+}
+
+#[test]
+fn should_not_pay_an_owner_the_token_invents_at_purchase() {
+    let mut fixture = MarketFixture::with_mock_token();
+    let (seller, buyer, bidder) = (fixture.seller, fixture.buyer, fixture.bidder);
+    fixture.listed_token("1", cspr(100));
+    let (seller_balance, bidder_balance) = (fixture.balance(seller), fixture.balance(bidder));
+
+    fixture.configure_mock(MockBehavior {
+        owner_override: Some(Key::Account(bidder)),
+        transfer: MockTransfer::Ignore,
+        ..Default::default()
+    });
+    assert_market_error(fixture.buy(buyer, "1", cspr(100)), MarketError::TokenNotTransferred);
+    assert_eq!(fixture.balance(seller), seller_balance);
+    assert_eq!(fixture.balance(bidder), bidder_balance);
+}
+
+#[test]
+fn should_not_let_a_reentrant_token_cancel_a_listing_mid_purchase() {
+    let mut fixture = MarketFixture::with_mock_token();
+    let (seller, buyer) = (fixture.seller, fixture.buyer);
+    fixture.listed_token("1", cspr(100));
+    let seller_balance = fixture.balance(seller);
+
+    fixture.configure_mock(MockBehavior { transfer: MockTransfer::Reenter("cancel_listing"), ..Default::default() });
+    assert_market_error(fixture.buy(buyer, "1", cspr(100)), MarketError::ReentrantCall);
+    assert_eq!(fixture.balance(seller), seller_balance);
+    assert_eq!(fixture.listing("1"), Some((Key::Account(seller), cspr(100))));
+}
+
+#[test]
+fn should_not_let_a_reentrant_token_withdraw_an_offer_mid_purchase() {
+    let mut fixture = MarketFixture::with_mock_token();
+    let (seller, buyer, bidder) = (fixture.seller, fixture.buyer, fixture.bidder);
+    fixture.listed_token("1", cspr(100));
+    fixture.offer(bidder, "1", cspr(5)).expect("offer should succeed");
+    let seller_balance = fixture.balance(seller);
+
+    fixture.configure_mock(MockBehavior { transfer: MockTransfer::Reenter("withdraw_offer"), ..Default::default() });
+    assert_market_error(fixture.buy(buyer, "1", cspr(100)), MarketError::ReentrantCall);
+
+    assert_eq!(fixture.balance(seller), seller_balance);
+    assert_eq!(fixture.offer_amount("1", bidder), Some(cspr(5)));
+    assert_eq!(fixture.market_purse_balance("offers_purse"), cspr(5));
+    assert_eq!(fixture.owner_of("1"), Some(Key::Account(seller)));
+}
+
+#[test]
+fn should_not_let_a_reentrant_token_accept_an_offer_twice() {
+    let mut fixture = MarketFixture::with_mock_token();
+    let (seller, bidder) = (fixture.seller, fixture.bidder);
+    offered_token(&mut fixture, "1");
+
+    // the nested accept names the same offer the outer one is settling
+    fixture.configure_mock(MockBehavior {
+        transfer: MockTransfer::Reenter("accept_offer"),
+        reenter_offer: Some(bidder),
+        ..Default::default()
+    });
+    assert_market_error(fixture.accept(seller, "1", bidder), MarketError::ReentrantCall);
+
+    assert_eq!(fixture.offer_amount("1", bidder), Some(cspr(5)));
+    assert_eq!(fixture.offer_bidders("1"), vec![Key::Account(bidder)]);
+    assert_eq!(fixture.market_purse_balance("offers_purse"), cspr(5));
+    assert_eq!(fixture.claimable(seller), cspr(0));
+    assert_eq!(fixture.owner_of("1"), Some(Key::Account(seller)));
+}
+
+#[test]
+fn should_keep_offers_when_an_accept_transfer_fails() {
+    let mut fixture = MarketFixture::with_mock_token();
+    let (seller, bidder) = (fixture.seller, fixture.bidder);
+    offered_token(&mut fixture, "1");
+
+    fixture.configure_mock(MockBehavior { transfer: MockTransfer::Revert, ..Default::default() });
+    assert_api_error(fixture.accept(seller, "1", bidder), ApiError::User(MOCK_TRANSFER_REFUSED));
+
+    fixture.configure_mock(MockBehavior { transfer: MockTransfer::Ignore, ..Default::default() });
+    assert_market_error(fixture.accept(seller, "1", bidder), MarketError::TokenNotTransferred);
+
+    fixture.configure_mock(MockBehavior { transfer: MockTransfer::Reenter("withdraw_offer"), ..Default::default() });
+    assert_market_error(fixture.accept(seller, "1", bidder), MarketError::ReentrantCall);
+
+    assert_eq!(fixture.offer_amount("1", bidder), Some(cspr(5)));
+    assert_eq!(fixture.offer_bidders("1"), vec![Key::Account(bidder)]);
+    assert_eq!(fixture.market_purse_balance("offers_purse"), cspr(5));
+    assert_eq!(fixture.claimable(seller), cspr(0));
+    assert_eq!(fixture.owner_of("1"), Some(Key::Account(seller)));
+}
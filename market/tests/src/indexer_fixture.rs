@@ -0,0 +1,102 @@
+//! The deploys behind `market/indexer/tests/fixtures`, run against the engine. `make
+//! indexer-fixture` from `market` records what they write over the fixture, so the indexer's
+//! tests decode real deploy results. Rerun it after a change to the market's events & commit it.
+
+use std::{env, fs};
+
+use casper_types::{account::AccountHash, ExecutionResult, Key};
+use market_core::Error as MarketError;
+use serde_json::{json, Value};
+
+use crate::fixture::{assert_market_error, cspr, MarketFixture};
+
+const DEPLOY_RESULTS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../indexer/tests/fixtures/deploy_results.json");
+const RECORDING_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../indexer/tests/fixtures/recording.json");
+// set to `record` to rewrite the fixture instead of only running the deploys
+const RECORD_VAR: &str = "INDEXER_FIXTURE";
+
+// the second bidder, clear of the fixture's own accounts
+const OTHER_SEED: u8 = 4;
+
+// the engine doesn't hash deploys or blocks like a node, these only tell the results apart
+const FIRST_DEPLOY: usize = 5000;
+const FIRST_BLOCK: usize = 9000;
+
+// the last deploy's result as the node reports it, every second one the way `info_get_deploy`
+// wraps it & the others the way a `DeployProcessed` event does, so both shapes are covered
+fn record(results: &mut Vec<Value>, fixture: &MarketFixture) {
+    let exec_results = fixture.builder.get_last_exec_results().expect("a deploy should have run");
+    let result = ExecutionResult::from(exec_results[0].as_ref());
+    let deploy_hash = format!("{:064x}", FIRST_DEPLOY + results.len());
+
+    results.push(if results.len() % 2 == 0 {
+        json!({ "DeployProcessed": { "deploy_hash": deploy_hash, "execution_result": result } })
+    } else {
+        let block_hash = format!("{:064x}", FIRST_BLOCK + results.len());
+        json!({
+            "deploy": { "hash": deploy_hash },
+            "execution_results": [{ "block_hash": block_hash, "result": result }]
+        })
+    });
+}
+
+fn account_arg(account: AccountHash) -> String {
+    Key::Account(account).to_formatted_string()
+}
+
+#[test]
+fn should_run_the_indexer_fixture_deploys() {
+    let mut fixture = MarketFixture::new();
+    let (seller, buyer, bidder) = (fixture.seller, fixture.buyer, fixture.bidder);
+    let other = fixture.new_account(OTHER_SEED);
+    for token_id in ["1", "2"] {
+        fixture.mint(seller, token_id);
+        fixture.approve_market(seller, token_id);
+    }
+    let mut results = Vec::new();
+
+    fixture.list(seller, "1", cspr(100)).expect("listing should succeed");
+    record(&mut results, &fixture);
+    fixture.list(seller, "2", cspr(50)).expect("listing should succeed");
+    record(&mut results, &fixture);
+    fixture.offer(bidder, "1", cspr(80)).expect("offer should succeed");
+    record(&mut results, &fixture);
+    fixture.offer(other, "1", cspr(90)).expect("offer should succeed");
+    record(&mut results, &fixture);
+    fixture.buy(buyer, "2", cspr(50)).expect("purchase should succeed");
+    record(&mut results, &fixture);
+    // a failed deploy writes no events
+    assert_market_error(fixture.buy(buyer, "1", cspr(10)), MarketError::BalanceInsufficient);
+    record(&mut results, &fixture);
+    fixture.accept(seller, "1", other).expect("accept should succeed");
+    record(&mut results, &fixture);
+
+    fixture.approve_market(buyer, "2");
+    fixture.list(buyer, "2", cspr(70)).expect("listing should succeed");
+    record(&mut results, &fixture);
+    fixture.offer(bidder, "2", cspr(60)).expect("offer should succeed");
+    record(&mut results, &fixture);
+    fixture.withdraw(bidder, "2").expect("withdraw should succeed");
+    record(&mut results, &fixture);
+    fixture.offer(bidder, "2", cspr(65)).expect("offer should succeed");
+    record(&mut results, &fixture);
+
+    assert_eq!(fixture.events_length(), 10);
+
+    if env::var(RECORD_VAR).map_or(false, |value| value == "record") {
+        let recording = json!({
+            "events_dictionary": hex::encode(fixture.events_dictionary()),
+            "token_contract": fixture.token_arg(),
+            "accounts": {
+                "seller": account_arg(seller),
+                "buyer": account_arg(buyer),
+                "bidder": account_arg(bidder),
+                "other": account_arg(other)
+            }
+        });
+        fs::write(DEPLOY_RESULTS_PATH, serde_json::to_string_pretty(&results).unwrap() + "\n")
+            .expect("deploy results should be writable");
+        fs::write(RECORDING_PATH, serde_json::to_string_pretty(&recording).unwrap() + "\n")
+            .expect("recording should be writable");
+    }
+}
@@ -1,116 +1,364 @@
 #[cfg(test)]
+mod fixture;
+
+#[cfg(test)]
+mod gas_tests;
+
+#[cfg(test)]
+mod hostile_token_tests;
+
+#[cfg(test)]
+mod indexer_fixture;
+
+/// Every market error a deploy can cause is hit here or in `hostile_token_tests`. Two can't be:
+/// `OfferPurseRetrieval` needs the market's `offers_purse` or `claims_purse` named key to hold
+/// something other than a purse, but only the market writes its named keys & it only ever puts a
+/// purse there. `ArithmeticOverflow` needs an amount past U512 (far beyond the motes in existence),
+/// 2^64 sales or 2^32 events, so it's covered against `MemoryHost` in `market/core/tests/market.rs`.
+#[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
-
-    use casper_engine_test_support::{
-        DeployItemBuilder, ExecuteRequestBuilder, InMemoryWasmTestBuilder, ARG_AMOUNT,
-        DEFAULT_ACCOUNT_ADDR, DEFAULT_ACCOUNT_INITIAL_BALANCE, DEFAULT_GENESIS_CONFIG,
-        DEFAULT_GENESIS_CONFIG_HASH, DEFAULT_PAYMENT, DEFAULT_RUN_GENESIS_REQUEST,
-    };
-    use casper_execution_engine::core::engine_state::{
-        run_genesis_request::RunGenesisRequest, GenesisAccount,
-    };
-    use casper_types::{
-        account::AccountHash, runtime_args, Key, Motes, PublicKey, RuntimeArgs, SecretKey, U512,
-    };
-
-    const MY_ACCOUNT: [u8; 32] = [7u8; 32];
-    // Define `KEY` constant to match that in the contract.
-    const KEY: &str = "my-key-name";
-    const VALUE: &str = "hello world";
-    const RUNTIME_ARG_NAME: &str = "message";
-    const CONTRACT_WASM: &str = "contract.wasm";
-
-    #[test]
-    fn should_store_hello_world() {
-        // Create keypair.
-        let secret_key = SecretKey::ed25519_from_bytes(MY_ACCOUNT).unwrap();
-        let public_key = PublicKey::from(&secret_key);
-
-        // Create an AccountHash from a public key.
-        let account_addr = AccountHash::from(&public_key);
-        // Create a GenesisAccount.
-        let account = GenesisAccount::account(
-            public_key,
-            Motes::new(U512::from(DEFAULT_ACCOUNT_INITIAL_BALANCE)),
-            None,
-        );
-
-        let mut genesis_config = DEFAULT_GENESIS_CONFIG.clone();
-        genesis_config.ee_config_mut().push_account(account);
-
-        let run_genesis_request = RunGenesisRequest::new(
-            *DEFAULT_GENESIS_CONFIG_HASH,
-            genesis_config.protocol_version(),
-            genesis_config.take_ee_config(),
-        );
-        // The test framework checks for compiled Wasm files in '<current working dir>/wasm'.  Paths
-        // relative to the current working dir (e.g. 'wasm/contract.wasm') can also be used, as can
-        // absolute paths.
-        let session_code = PathBuf::from(CONTRACT_WASM);
-        let session_args = runtime_args! {
-            RUNTIME_ARG_NAME => VALUE,
-        };
-
-        let deploy_item = DeployItemBuilder::new()
-            .with_empty_payment_bytes(runtime_args! {
-                ARG_AMOUNT => *DEFAULT_PAYMENT
-            })
-            .with_session_code(session_code, session_args)
-            .with_authorization_keys(&[account_addr])
-            .with_address(account_addr)
-            .build();
-
-        let execute_request = ExecuteRequestBuilder::from_deploy_item(deploy_item).build();
-
-        let mut builder = InMemoryWasmTestBuilder::default();
-        builder.run_genesis(&run_genesis_request).commit();
-
-        // prepare assertions.
-        let result_of_query = builder.query(
-            None,
-            Key::Account(*DEFAULT_ACCOUNT_ADDR),
-            &[KEY.to_string()],
-        );
-        assert!(result_of_query.is_err());
-
-        // deploy the contract.
-        builder.exec(execute_request).commit().expect_success();
-
-        // make assertions
-        let result_of_query = builder
-            .query(None, Key::Account(account_addr), &[KEY.to_string()])
-            .expect("should be stored value.")
-            .as_cl_value()
-            .expect("should be cl value.")
-            .clone()
-            .into_t::<String>()
-            .expect("should be string.");
-
-        assert_eq!(result_of_query, VALUE);
-    }
-
-    #[test]
-    fn should_error_on_missing_runtime_arg() {
-        let secret_key = SecretKey::ed25519_from_bytes(MY_ACCOUNT).unwrap();
-        let public_key = PublicKey::from(&secret_key);
-        let account_addr = AccountHash::from(&public_key);
-
-        let session_code = PathBuf::from(CONTRACT_WASM);
-        let session_args = RuntimeArgs::new();
-
-        let deploy_item = DeployItemBuilder::new()
-            .with_empty_payment_bytes(runtime_args! {ARG_AMOUNT => *DEFAULT_PAYMENT})
-            .with_authorization_keys(&[account_addr])
-            .with_address(*DEFAULT_ACCOUNT_ADDR)
-            .with_session_code(session_code, session_args)
-            .build();
-
-        let execute_request = ExecuteRequestBuilder::from_deploy_item(deploy_item).build();
-
-        let mut builder = InMemoryWasmTestBuilder::default();
-        builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
-        builder.exec(execute_request).commit().expect_failure();
+    use casper_types::{runtime_args, Key, RuntimeArgs, U256, U512};
+
+    use crate::fixture::{assert_market_error, cspr, MarketError, MarketFixture, MIN_OFFER_AMOUNT};
+
+    #[test]
+    fn should_install_the_market() {
+        let fixture = MarketFixture::new();
+
+        assert_eq!(fixture.events_length(), 0);
+        assert_eq!(fixture.listing("1"), None);
+        assert_eq!(fixture.market_purse_balance("offers_purse"), U512::zero());
+    }
+
+    #[test]
+    fn should_list_and_buy_a_token() {
+        let mut fixture = MarketFixture::new();
+        let (seller, buyer) = (fixture.seller, fixture.buyer);
+
+        fixture.listed_token("1", cspr(100));
+        assert_eq!(fixture.listing("1"), Some((Key::Account(seller), cspr(100))));
+
+        let seller_balance = fixture.balance(seller);
+        let buyer_balance = fixture.balance(buyer);
+        fixture.buy(buyer, "1", cspr(100)).expect("buy should succeed");
+
+        assert_eq!(fixture.owner_of("1"), Some(Key::Account(buyer)));
+        assert_eq!(fixture.listing("1"), None);
+        // the seller didn't pay for the buyer's deploy so their balance moves by exactly the price
+        assert_eq!(fixture.balance(seller), seller_balance + cspr(100));
+        assert!(buyer_balance - fixture.balance(buyer) >= cspr(100));
+        assert_eq!(fixture.events_length(), 2);
+    }
+
+    #[test]
+    fn should_not_sell_a_listing_twice() {
+        let mut fixture = MarketFixture::new();
+        let (buyer, bidder) = (fixture.buyer, fixture.bidder);
+
+        fixture.listed_token("1", cspr(100));
+        fixture.buy(buyer, "1", cspr(100)).expect("buy should succeed");
+
+        assert_market_error(fixture.buy(bidder, "1", cspr(100)), MarketError::ListingCanceledOrSold);
+        assert_eq!(fixture.owner_of("1"), Some(Key::Account(buyer)));
+    }
+
+    #[test]
+    fn should_not_buy_an_unlisted_token() {
+        let mut fixture = MarketFixture::new();
+        let (seller, buyer) = (fixture.seller, fixture.buyer);
+        fixture.mint(seller, "1");
+
+        assert_market_error(fixture.buy(buyer, "1", cspr(100)), MarketError::ListingDoesNotExist);
+    }
+
+    #[test]
+    fn should_not_buy_below_the_listing_price() {
+        let mut fixture = MarketFixture::new();
+        let (seller, buyer) = (fixture.seller, fixture.buyer);
+
+        fixture.listed_token("1", cspr(100));
+        let seller_balance = fixture.balance(seller);
+
+        assert_market_error(fixture.buy(buyer, "1", cspr(99)), MarketError::BalanceInsufficient);
+        assert_eq!(fixture.owner_of("1"), Some(Key::Account(seller)));
+        assert_eq!(fixture.balance(seller), seller_balance);
+        assert!(fixture.listing("1").is_some());
+    }
+
+    #[test]
+    fn should_cancel_a_listing() {
+        let mut fixture = MarketFixture::new();
+        let (seller, buyer) = (fixture.seller, fixture.buyer);
+
+        fixture.listed_token("1", cspr(100));
+        fixture.cancel(seller, "1").expect("cancel should succeed");
+
+        assert_eq!(fixture.listing("1"), None);
+        assert_market_error(fixture.buy(buyer, "1", cspr(100)), MarketError::ListingCanceledOrSold);
+        assert_eq!(fixture.owner_of("1"), Some(Key::Account(seller)));
+    }
+
+    #[test]
+    fn should_only_let_the_owner_list_or_cancel() {
+        let mut fixture = MarketFixture::new();
+        let (seller, buyer) = (fixture.seller, fixture.buyer);
+
+        fixture.listed_token("1", cspr(100));
+
+        assert_market_error(fixture.cancel(buyer, "1"), MarketError::PermissionDenied);
+        assert_market_error(fixture.list(buyer, "1", cspr(1)), MarketError::PermissionDenied);
+        assert_eq!(fixture.listing("1"), Some((Key::Account(seller), cspr(100))));
+    }
+
+    #[test]
+    fn should_need_the_market_approved_to_list() {
+        let mut fixture = MarketFixture::new();
+        let (seller, buyer) = (fixture.seller, fixture.buyer);
+        fixture.mint(seller, "1");
+
+        assert_market_error(fixture.list(seller, "1", cspr(100)), MarketError::NeedsTransferApproval);
+
+        // approving an account leaves no contract hash to compare against
+        fixture.approve(seller, Key::Account(buyer), "1");
+        assert_market_error(fixture.list(seller, "1", cspr(100)), MarketError::MissingApprovalResult);
+
+        fixture.approve(seller, Key::Hash([9; 32]), "1");
+        assert_market_error(fixture.list(seller, "1", cspr(100)), MarketError::NeedsTransferApproval);
+
+        fixture.approve_market(seller, "1");
+        fixture.list(seller, "1", cspr(100)).expect("listing should succeed");
+    }
+
+    #[test]
+    fn should_reject_malformed_listing_args() {
+        let mut fixture = MarketFixture::new();
+        let seller = fixture.seller;
+        fixture.mint(seller, "1");
+        fixture.approve_market(seller, "1");
+
+        let result = fixture.call_market(seller, "create_listing", runtime_args! {
+            "token_contract_hash" => "not-a-contract".to_string(),
+            "token_id" => "1".to_string(),
+            "price" => cspr(100)
+        });
+        assert_market_error(result, MarketError::InvalidTokenContractHash);
+
+        assert_market_error(fixture.list(seller, "one", cspr(100)), MarketError::InvalidTokenId);
+        // the token contract has no owner for a token that was never minted
+        assert_market_error(fixture.list(seller, "99", cspr(100)), MarketError::InvalidTokenId);
+    }
+
+    #[test]
+    fn should_only_accept_a_purse() {
+        let mut fixture = MarketFixture::new();
+        let admin = fixture.admin;
+        fixture.listed_token("1", cspr(100));
+
+        // a uref the admin legitimately holds that isn't a purse
+        let not_a_purse = fixture.builder.get_expected_account(admin)
+            .named_keys()
+            .get("market_contract_hash_wrapped")
+            .and_then(|key| key.into_uref())
+            .unwrap();
+        let result = fixture.call_market(admin, "buy_listing", runtime_args! {
+            "token_contract_hash" => fixture.token_arg(),
+            "token_id" => "1".to_string(),
+            "purse" => not_a_purse
+        });
+        assert_market_error(result, MarketError::InvalidPurse);
+    }
+
+    #[test]
+    fn should_hold_offers_in_escrow_until_withdrawn() {
+        let mut fixture = MarketFixture::new();
+        let (seller, bidder) = (fixture.seller, fixture.bidder);
+        fixture.mint(seller, "1");
+
+        fixture.offer(bidder, "1", cspr(50)).expect("offer should succeed");
+        assert_eq!(fixture.offer_amount("1", bidder), Some(cspr(50)));
+        assert_eq!(fixture.market_purse_balance("offers_purse"), cspr(50));
+
+        let bidder_balance = fixture.balance(bidder);
+        fixture.withdraw(bidder, "1").expect("withdraw should succeed");
+
+        assert_eq!(fixture.offer_amount("1", bidder), None);
+        assert_eq!(fixture.market_purse_balance("offers_purse"), U512::zero());
+        // refunded straight to the bidder, less what the withdraw deploy cost
+        assert!(fixture.balance(bidder) > bidder_balance);
+        assert_market_error(fixture.withdraw(bidder, "1"), MarketError::NoMatchingOffer);
+    }
+
+    #[test]
+    fn should_accept_an_offer_and_credit_the_seller() {
+        let mut fixture = MarketFixture::new();
+        let (seller, buyer, bidder) = (fixture.seller, fixture.buyer, fixture.bidder);
+        fixture.mint(seller, "1");
+
+        fixture.offer(bidder, "1", cspr(50)).expect("offer should succeed");
+        fixture.offer(buyer, "1", cspr(40)).expect("offer should succeed");
+        assert_eq!(fixture.market_purse_balance("offers_purse"), cspr(90));
+
+        fixture.approve_market(seller, "1");
+        fixture.accept(seller, "1", bidder).expect("accept should succeed");
+
+        assert_eq!(fixture.owner_of("1"), Some(Key::Account(bidder)));
+        assert_eq!(fixture.offer_amount("1", bidder), None);
+        // the other offer stays open for its bidder
+        assert_eq!(fixture.offer_amount("1", buyer), Some(cspr(40)));
+        assert_eq!(fixture.market_purse_balance("offers_purse"), cspr(40));
+        assert_eq!(fixture.market_purse_balance("claims_purse"), cspr(50));
+        assert_eq!(fixture.claimable(seller), cspr(50));
+        assert_eq!(fixture.claimable(buyer), U512::zero());
+
+        let seller_balance = fixture.balance(seller);
+        fixture.claim(seller).expect("claim should succeed");
+        assert_eq!(fixture.claimable(seller), U512::zero());
+        assert_eq!(fixture.market_purse_balance("claims_purse"), U512::zero());
+        assert!(fixture.balance(seller) > seller_balance);
+
+        assert_market_error(fixture.claim(seller), MarketError::NothingToClaim);
+        fixture.withdraw(buyer, "1").expect("withdraw should succeed");
+        assert_eq!(fixture.market_purse_balance("offers_purse"), U512::zero());
+    }
+
+    #[test]
+    fn should_drop_the_listing_when_an_offer_is_accepted() {
+        let mut fixture = MarketFixture::new();
+        let (seller, buyer, bidder) = (fixture.seller, fixture.buyer, fixture.bidder);
+
+        fixture.listed_token("1", cspr(100));
+        fixture.offer(bidder, "1", cspr(90)).expect("offer should succeed");
+        fixture.accept(seller, "1", bidder).expect("accept should succeed");
+
+        assert_eq!(fixture.listing("1"), None);
+        assert_market_error(fixture.buy(buyer, "1", cspr(100)), MarketError::ListingCanceledOrSold);
+    }
+
+    #[test]
+    fn should_enforce_offer_limits() {
+        let mut fixture = MarketFixture::new();
+        let (admin, seller, buyer, bidder) = (fixture.admin, fixture.seller, fixture.buyer, fixture.bidder);
+        fixture.mint(seller, "1");
+
+        let too_low = U512::from(MIN_OFFER_AMOUNT) - 1;
+        assert_market_error(fixture.offer(bidder, "1", too_low), MarketError::OfferTooLow);
+
+        fixture.offer(bidder, "1", cspr(10)).expect("offer should succeed");
+        assert_market_error(fixture.offer(bidder, "1", cspr(20)), MarketError::OfferExists);
+
+        // MAX_OFFERS_PER_TOKEN is 3
+        fixture.offer(buyer, "1", cspr(10)).expect("offer should succeed");
+        fixture.offer(admin, "1", cspr(10)).expect("offer should succeed");
+        assert_market_error(fixture.offer(seller, "1", cspr(10)), MarketError::TooManyOffers);
+
+        // a withdrawn offer frees its slot
+        fixture.withdraw(admin, "1").expect("withdraw should succeed");
+        fixture.offer(seller, "1", cspr(10)).expect("offer should succeed");
+
+        assert_market_error(fixture.offer(bidder, "one", cspr(10)), MarketError::InvalidTokenId);
+        assert_eq!(fixture.market_purse_balance("offers_purse"), cspr(30));
+    }
+
+    #[test]
+    fn should_only_accept_an_existing_offer() {
+        let mut fixture = MarketFixture::new();
+        let (seller, buyer, bidder) = (fixture.seller, fixture.buyer, fixture.bidder);
+        fixture.mint(seller, "1");
+        fixture.approve_market(seller, "1");
+        fixture.offer(bidder, "1", cspr(10)).expect("offer should succeed");
+
+        assert_market_error(fixture.accept(seller, "1", buyer), MarketError::NoMatchingOffer);
+
+        let result = fixture.call_market(seller, "accept_offer", runtime_args! {
+            "token_contract_hash" => fixture.token_arg(),
+            "token_id" => "1".to_string(),
+            "accepted_offer" => "not-a-key".to_string()
+        });
+        assert_market_error(result, MarketError::InvalidAccountKey);
+
+        assert_eq!(fixture.owner_of("1"), Some(Key::Account(seller)));
+        assert_eq!(fixture.offer_amount("1", bidder), Some(cspr(10)));
+    }
+
+    fn custody_listed(fixture: &mut MarketFixture, token_id: &str, price: U512) {
+        let seller = fixture.seller;
+        fixture.mint(seller, token_id);
+        fixture.approve_market(seller, token_id);
+        fixture.list_in_custody(seller, token_id, price).expect("custody listing should succeed");
+    }
+
+    #[test]
+    fn should_hold_a_custody_listed_token_until_it_sells() {
+        let mut fixture = MarketFixture::new();
+        let (seller, buyer, bidder) = (fixture.seller, fixture.buyer, fixture.bidder);
+
+        custody_listed(&mut fixture, "1", cspr(100));
+        assert_eq!(fixture.owner_of("1"), Some(Key::from(fixture.market_package)));
+        assert_eq!(fixture.listing("1"), Some((Key::Account(seller), cspr(100))));
+        assert!(fixture.in_custody("1"));
+
+        // the seller can't move it out from under the listing anymore
+        let moved = fixture.call_token(seller, "transfer", runtime_args! {
+            "recipient" => Key::Account(bidder),
+            "token_ids" => vec![U256::one()]
+        });
+        assert!(moved.is_err());
+
+        let seller_balance = fixture.balance(seller);
+        fixture.buy(buyer, "1", cspr(100)).expect("buy should succeed");
+        assert_eq!(fixture.owner_of("1"), Some(Key::Account(buyer)));
+        assert_eq!(fixture.balance(seller), seller_balance + cspr(100));
+        assert_eq!(fixture.listing("1"), None);
+        assert!(!fixture.in_custody("1"));
+    }
+
+    #[test]
+    fn should_hand_a_custody_token_back_on_cancel() {
+        let mut fixture = MarketFixture::new();
+        let (seller, buyer) = (fixture.seller, fixture.buyer);
+
+        custody_listed(&mut fixture, "1", cspr(100));
+        assert_market_error(fixture.cancel(buyer, "1"), MarketError::PermissionDenied);
+
+        fixture.cancel(seller, "1").expect("cancel should succeed");
+        assert_eq!(fixture.owner_of("1"), Some(Key::Account(seller)));
+        assert_eq!(fixture.listing("1"), None);
+        assert!(!fixture.in_custody("1"));
+    }
+
+    #[test]
+    fn should_deliver_a_custody_token_to_an_accepted_offer() {
+        let mut fixture = MarketFixture::new();
+        let (seller, buyer, bidder) = (fixture.seller, fixture.buyer, fixture.bidder);
+
+        custody_listed(&mut fixture, "1", cspr(100));
+        fixture.offer(bidder, "1", cspr(10)).expect("offer should succeed");
+        assert_market_error(fixture.accept(buyer, "1", bidder), MarketError::PermissionDenied);
+
+        fixture.accept(seller, "1", bidder).expect("accept should succeed");
+        assert_eq!(fixture.owner_of("1"), Some(Key::Account(bidder)));
+        assert_eq!(fixture.claimable(seller), cspr(10));
+        assert_eq!(fixture.listing("1"), None);
+        assert!(!fixture.in_custody("1"));
+    }
+
+    #[test]
+    fn should_buy_the_floor() {
+        let mut fixture = MarketFixture::new();
+        let (seller, buyer) = (fixture.seller, fixture.buyer);
+
+        fixture.listed_token("1", cspr(100));
+        fixture.listed_token("2", cspr(80));
+
+        // the payment wasm passes its amount on as `max_price`
+        assert_market_error(fixture.pay_market(buyer, "buy_floor", "", cspr(50)), MarketError::FloorAboveMaxPrice);
+
+        fixture.pay_market(buyer, "buy_floor", "", cspr(80)).expect("buy_floor should succeed");
+        assert_eq!(fixture.owner_of("2"), Some(Key::Account(buyer)));
+        assert_eq!(fixture.listing("2"), None);
+
+        fixture.cancel(seller, "1").expect("cancel should succeed");
+        assert_market_error(fixture.pay_market(buyer, "buy_floor", "", cspr(100)), MarketError::NoActiveListings);
     }
 }
 
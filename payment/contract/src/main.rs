@@ -17,12 +17,13 @@ use casper_contract::{
 
 use casper_types::{
     runtime_args, RuntimeArgs,
-    ContractHash, U512
+    ApiError, ContractHash, U512
 };
 
 const NFT_CONTRACT_HASH_ARG: &str = "token_contract_hash";
 const TOKEN_ID_ARG: &str = "token_id";
 const AMOUNT_ARG: &str = "amount";
+const MAX_PRICE_ARG: &str = "max_price";
 
 const MARKET_CONTRACT_HASH_ARG: &str = "market_contract_hash";
 const MARKET_ENTRY_POINT_NAME_ARG: &str = "entry_point_name";
@@ -34,7 +35,8 @@ pub extern "C" fn call() {
     let token_id: String = runtime::get_named_arg(TOKEN_ID_ARG);
 
     let market_contract_hash_arg: String = runtime::get_named_arg(MARKET_CONTRACT_HASH_ARG);
-    let market_contract_hash: ContractHash = ContractHash::from_formatted_str(&market_contract_hash_arg).unwrap();
+    let market_contract_hash: ContractHash = ContractHash::from_formatted_str(&market_contract_hash_arg)
+        .unwrap_or_revert_with(ApiError::InvalidArgument);
     let market_entry_point_name: String = runtime::get_named_arg(MARKET_ENTRY_POINT_NAME_ARG);
 
     let new_purse = system::create_purse();
@@ -42,9 +44,12 @@ pub extern "C" fn call() {
     system::transfer_from_purse_to_purse(account::get_main_purse(), new_purse, amount, None)
         .unwrap_or_revert();
         
+    // the purse is funded with `amount` so that is also the most `buy_floor` may spend,
+    // the other entry points ignore `max_price`
     runtime::call_contract(market_contract_hash, &market_entry_point_name, runtime_args! {
         "purse" => new_purse,
         "token_contract_hash" => token_contract_hash,
-        "token_id" => token_id
+        "token_id" => token_id,
+        MAX_PRICE_ARG => amount
     })
 }
\ No newline at end of file
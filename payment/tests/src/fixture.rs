@@ -0,0 +1,194 @@
+//! Runs the payment wasm as session code against a market installed in the in-memory engine,
+//! with a CEP-47 collection to list & bid on.
+//!
+//! `make test` copies the payment & market wasm into `tests/wasm`, along with the `cep47-token.wasm`
+//! built from https://github.com/casper-ecosystem/casper-nft-cep47 (see `CEP47_WASM` in the Makefile).
+
+use std::collections::BTreeMap;
+
+use casper_engine_test_support::{
+    ExecuteRequestBuilder, InMemoryWasmTestBuilder, DEFAULT_ACCOUNT_ADDR, DEFAULT_RUN_GENESIS_REQUEST
+};
+use casper_execution_engine::core::{
+    engine_state::{Error as EngineError, ExecuteRequest},
+    execution::Error as ExecError
+};
+use casper_types::{
+    account::AccountHash,
+    runtime_args, system::mint, ApiError, ContractHash, ContractPackageHash, Key, RuntimeArgs,
+    U256, U512
+};
+
+pub const PAYMENT_WASM: &str = "payment.wasm";
+pub const MARKET_WASM: &str = "market.wasm";
+pub const CEP47_WASM: &str = "cep47-token.wasm";
+
+const CEP47_CONTRACT_NAME: &str = "payment_test_nft";
+
+// 100k CSPR per test account
+const ACCOUNT_FUNDING: u64 = 100_000_000_000_000;
+
+pub fn cspr(amount: u64) -> U512 {
+    U512::from(amount) * U512::from(1_000_000_000u64)
+}
+
+pub fn assert_revert(result: Result<(), EngineError>, expected: ApiError) {
+    match result {
+        Err(EngineError::Exec(ExecError::Revert(error))) => assert_eq!(error, expected),
+        other => panic!("expected {:?}, got {:?}", expected, other)
+    }
+}
+
+pub struct PaymentFixture {
+    pub builder: InMemoryWasmTestBuilder,
+    pub market: ContractHash,
+    pub market_package: ContractPackageHash,
+    pub token: ContractHash,
+    pub admin: AccountHash,
+    pub seller: AccountHash,
+    pub buyer: AccountHash
+}
+
+impl PaymentFixture {
+    pub fn new() -> Self {
+        let mut builder = InMemoryWasmTestBuilder::default();
+        builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
+
+        let admin = *DEFAULT_ACCOUNT_ADDR;
+        let seller = AccountHash::new([1; 32]);
+        let buyer = AccountHash::new([2; 32]);
+
+        for account in [seller, buyer] {
+            let transfer = ExecuteRequestBuilder::transfer(admin, runtime_args! {
+                mint::ARG_AMOUNT => U512::from(ACCOUNT_FUNDING),
+                mint::ARG_TARGET => account,
+                mint::ARG_ID => Option::<u64>::None
+            }).build();
+            builder.exec(transfer).expect_success().commit();
+        }
+
+        let install_token = ExecuteRequestBuilder::standard(admin, CEP47_WASM, runtime_args! {
+            "name" => "Payment Test NFT".to_string(),
+            "symbol" => "PTN".to_string(),
+            "meta" => BTreeMap::<String, String>::new(),
+            "contract_name" => CEP47_CONTRACT_NAME.to_string()
+        }).build();
+        builder.exec(install_token).expect_success().commit();
+
+        let install_market = ExecuteRequestBuilder::standard(admin, MARKET_WASM, runtime_args! {
+            "max_offers_per_token" => 3u32,
+            "min_offer_amount" => cspr(1)
+        }).build();
+        builder.exec(install_market).expect_success().commit();
+
+        let admin_account = builder.get_expected_account(admin);
+        let named_hash = |name: &str| admin_account.named_keys()
+            .get(name)
+            .and_then(|key| key.into_hash())
+            .unwrap_or_else(|| panic!("installer should have {}", name));
+
+        PaymentFixture {
+            market: ContractHash::new(named_hash("market_contract_hash")),
+            market_package: ContractPackageHash::new(named_hash("market_contract_package_hash")),
+            token: ContractHash::new(named_hash(&format!("{}_contract_hash", CEP47_CONTRACT_NAME))),
+            builder,
+            admin,
+            seller,
+            buyer
+        }
+    }
+
+    pub fn exec(&mut self, request: ExecuteRequest) -> Result<(), EngineError> {
+        self.builder.exec(request).commit();
+        match self.builder.get_error() {
+            Some(error) => Err(error),
+            None => Ok(())
+        }
+    }
+
+    fn call_contract(&mut self, sender: AccountHash, contract: ContractHash, entry_point: &str, args: RuntimeArgs) {
+        let request = ExecuteRequestBuilder::contract_call_by_hash(sender, contract, entry_point, args).build();
+        self.exec(request).unwrap_or_else(|error| panic!("{} should succeed: {:?}", entry_point, error));
+    }
+
+    /// Mints to the seller, approves the market & lists at `price`.
+    pub fn listed_token(&mut self, token_id: &str, price: U512) {
+        let mut meta = BTreeMap::new();
+        meta.insert("name".to_string(), format!("token {}", token_id));
+        self.call_contract(self.admin, self.token, "mint", runtime_args! {
+            "recipient" => Key::Account(self.seller),
+            "token_ids" => vec![U256::from_dec_str(token_id).unwrap()],
+            "token_metas" => vec![meta]
+        });
+        self.call_contract(self.seller, self.token, "approve", runtime_args! {
+            "spender" => Key::from(self.market_package),
+            "token_ids" => vec![U256::from_dec_str(token_id).unwrap()]
+        });
+        self.call_contract(self.seller, self.market, "create_listing", runtime_args! {
+            "token_contract_hash" => self.token.to_formatted_string(),
+            "token_id" => token_id.to_string(),
+            "price" => price
+        });
+    }
+
+    /// Runs the payment wasm with every arg spelled out, so tests can break any one of them.
+    pub fn pay_with(
+        &mut self,
+        sender: AccountHash,
+        market_contract_hash: String,
+        entry_point: &str,
+        token_id: &str,
+        amount: U512
+    ) -> Result<(), EngineError> {
+        let request = ExecuteRequestBuilder::standard(sender, PAYMENT_WASM, runtime_args! {
+            "market_contract_hash" => market_contract_hash,
+            "entry_point_name" => entry_point.to_string(),
+            "token_contract_hash" => self.token.to_formatted_string(),
+            "token_id" => token_id.to_string(),
+            "amount" => amount
+        }).build();
+        self.exec(request)
+    }
+
+    pub fn pay(&mut self, sender: AccountHash, entry_point: &str, token_id: &str, amount: U512) -> Result<(), EngineError> {
+        let market = self.market.to_formatted_string();
+        self.pay_with(sender, market, entry_point, token_id, amount)
+    }
+
+    pub fn balance(&self, account: AccountHash) -> U512 {
+        let purse = self.builder.get_expected_account(account).main_purse();
+        self.builder.get_purse_balance(purse)
+    }
+
+    /// What the last deploy paid for gas, read off the block proposer's purse.
+    pub fn fee_of<T>(&mut self, run: impl FnOnce(&mut Self) -> T) -> (T, U512) {
+        let before = self.builder.get_proposer_purse_balance();
+        let result = run(self);
+        (result, self.builder.get_proposer_purse_balance() - before)
+    }
+
+    /// Balance of the market's `offers_purse`, zero until the first offer creates it.
+    pub fn offers_purse_balance(&self) -> U512 {
+        self.builder.get_contract(self.market)
+            .expect("market should be installed")
+            .named_keys()
+            .get("offers_purse")
+            .and_then(|key| key.into_uref())
+            .map_or_else(U512::zero, |purse| self.builder.get_purse_balance(purse))
+    }
+
+    /// Reads the CEP-47 `owners` dictionary directly, `owner_of` can't return to a deploy.
+    pub fn owner_of(&self, token_id: &str) -> Option<Key> {
+        let owners_uref = self.builder.get_contract(self.token)
+            .expect("token should be installed")
+            .named_keys()
+            .get("owners")
+            .and_then(|key| key.into_uref())?;
+        let value = self.builder.query_dictionary_item(None, owners_uref, token_id)
+            .ok()?
+            .as_cl_value()
+            .cloned()?;
+        value.clone().into_t::<Key>().ok()
+            .or_else(|| value.into_t::<Option<Key>>().ok().flatten())
+    }
+}
@@ -1,116 +1,78 @@
+#[cfg(test)]
+mod fixture;
+
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
-
-    use casper_engine_test_support::{
-        DeployItemBuilder, ExecuteRequestBuilder, InMemoryWasmTestBuilder, ARG_AMOUNT,
-        DEFAULT_ACCOUNT_ADDR, DEFAULT_ACCOUNT_INITIAL_BALANCE, DEFAULT_GENESIS_CONFIG,
-        DEFAULT_GENESIS_CONFIG_HASH, DEFAULT_PAYMENT, DEFAULT_RUN_GENESIS_REQUEST,
-    };
-    use casper_execution_engine::core::engine_state::{
-        run_genesis_request::RunGenesisRequest, GenesisAccount,
-    };
-    use casper_types::{
-        account::AccountHash, runtime_args, Key, Motes, PublicKey, RuntimeArgs, SecretKey, U512,
-    };
-
-    const MY_ACCOUNT: [u8; 32] = [7u8; 32];
-    // Define `KEY` constant to match that in the contract.
-    const KEY: &str = "my-key-name";
-    const VALUE: &str = "hello world";
-    const RUNTIME_ARG_NAME: &str = "message";
-    const CONTRACT_WASM: &str = "contract.wasm";
+    use casper_types::{ApiError, ContractHash, Key};
+
+    use crate::fixture::{assert_revert, cspr, PaymentFixture};
+
+    // market `Error::BalanceInsufficient`, see `market/core/src/error.rs`
+    const BALANCE_INSUFFICIENT: u16 = 1002;
+
+    #[test]
+    fn should_fund_an_offer_with_exactly_the_amount() {
+        let mut fixture = PaymentFixture::new();
+        fixture.listed_token("1", cspr(100));
+        let buyer = fixture.buyer;
+        let before = fixture.balance(buyer);
+
+        let (result, fee) = fixture.fee_of(|fixture| fixture.pay(buyer, "make_offer", "1", cspr(40)));
+        result.expect("make_offer should succeed");
+
+        assert_eq!(fixture.offers_purse_balance(), cspr(40));
+        assert_eq!(fixture.balance(buyer), before - cspr(40) - fee);
+    }
+
+    #[test]
+    fn should_pay_exactly_the_price_for_a_listing() {
+        let mut fixture = PaymentFixture::new();
+        fixture.listed_token("1", cspr(100));
+        let (buyer, seller) = (fixture.buyer, fixture.seller);
+        let (buyer_before, seller_before) = (fixture.balance(buyer), fixture.balance(seller));
+
+        let (result, fee) = fixture.fee_of(|fixture| fixture.pay(buyer, "buy_listing", "1", cspr(100)));
+        result.expect("buy_listing should succeed");
+
+        assert_eq!(fixture.owner_of("1"), Some(Key::Account(buyer)));
+        assert_eq!(fixture.balance(buyer), buyer_before - cspr(100) - fee);
+        assert_eq!(fixture.balance(seller), seller_before + cspr(100));
+    }
 
     #[test]
-    fn should_store_hello_world() {
-        // Create keypair.
-        let secret_key = SecretKey::ed25519_from_bytes(MY_ACCOUNT).unwrap();
-        let public_key = PublicKey::from(&secret_key);
-
-        // Create an AccountHash from a public key.
-        let account_addr = AccountHash::from(&public_key);
-        // Create a GenesisAccount.
-        let account = GenesisAccount::account(
-            public_key,
-            Motes::new(U512::from(DEFAULT_ACCOUNT_INITIAL_BALANCE)),
-            None,
-        );
-
-        let mut genesis_config = DEFAULT_GENESIS_CONFIG.clone();
-        genesis_config.ee_config_mut().push_account(account);
-
-        let run_genesis_request = RunGenesisRequest::new(
-            *DEFAULT_GENESIS_CONFIG_HASH,
-            genesis_config.protocol_version(),
-            genesis_config.take_ee_config(),
-        );
-        // The test framework checks for compiled Wasm files in '<current working dir>/wasm'.  Paths
-        // relative to the current working dir (e.g. 'wasm/contract.wasm') can also be used, as can
-        // absolute paths.
-        let session_code = PathBuf::from(CONTRACT_WASM);
-        let session_args = runtime_args! {
-            RUNTIME_ARG_NAME => VALUE,
-        };
-
-        let deploy_item = DeployItemBuilder::new()
-            .with_empty_payment_bytes(runtime_args! {
-                ARG_AMOUNT => *DEFAULT_PAYMENT
-            })
-            .with_session_code(session_code, session_args)
-            .with_authorization_keys(&[account_addr])
-            .with_address(account_addr)
-            .build();
-
-        let execute_request = ExecuteRequestBuilder::from_deploy_item(deploy_item).build();
-
-        let mut builder = InMemoryWasmTestBuilder::default();
-        builder.run_genesis(&run_genesis_request).commit();
-
-        // prepare assertions.
-        let result_of_query = builder.query(
-            None,
-            Key::Account(*DEFAULT_ACCOUNT_ADDR),
-            &[KEY.to_string()],
-        );
-        assert!(result_of_query.is_err());
-
-        // deploy the contract.
-        builder.exec(execute_request).commit().expect_success();
-
-        // make assertions
-        let result_of_query = builder
-            .query(None, Key::Account(account_addr), &[KEY.to_string()])
-            .expect("should be stored value.")
-            .as_cl_value()
-            .expect("should be cl value.")
-            .clone()
-            .into_t::<String>()
-            .expect("should be string.");
-
-        assert_eq!(result_of_query, VALUE);
+    fn should_strand_nothing_when_the_market_reverts() {
+        let mut fixture = PaymentFixture::new();
+        fixture.listed_token("1", cspr(100));
+        let buyer = fixture.buyer;
+        let before = fixture.balance(buyer);
+
+        let (result, fee) = fixture.fee_of(|fixture| fixture.pay(buyer, "buy_listing", "1", cspr(99)));
+        assert_revert(result, ApiError::User(BALANCE_INSUFFICIENT));
+
+        assert_eq!(fixture.balance(buyer), before - fee);
+        assert_eq!(fixture.owner_of("1"), Some(Key::Account(fixture.seller)));
+        assert_eq!(fixture.offers_purse_balance(), 0.into());
     }
 
     #[test]
-    fn should_error_on_missing_runtime_arg() {
-        let secret_key = SecretKey::ed25519_from_bytes(MY_ACCOUNT).unwrap();
-        let public_key = PublicKey::from(&secret_key);
-        let account_addr = AccountHash::from(&public_key);
-
-        let session_code = PathBuf::from(CONTRACT_WASM);
-        let session_args = RuntimeArgs::new();
-
-        let deploy_item = DeployItemBuilder::new()
-            .with_empty_payment_bytes(runtime_args! {ARG_AMOUNT => *DEFAULT_PAYMENT})
-            .with_authorization_keys(&[account_addr])
-            .with_address(*DEFAULT_ACCOUNT_ADDR)
-            .with_session_code(session_code, session_args)
-            .build();
-
-        let execute_request = ExecuteRequestBuilder::from_deploy_item(deploy_item).build();
-
-        let mut builder = InMemoryWasmTestBuilder::default();
-        builder.run_genesis(&DEFAULT_RUN_GENESIS_REQUEST).commit();
-        builder.exec(execute_request).commit().expect_failure();
+    fn should_strand_nothing_when_the_market_hash_is_malformed() {
+        let mut fixture = PaymentFixture::new();
+        fixture.listed_token("1", cspr(100));
+        let buyer = fixture.buyer;
+        let before = fixture.balance(buyer);
+
+        let (result, fee) = fixture.fee_of(|fixture| {
+            fixture.pay_with(buyer, "not-a-contract-hash".to_string(), "buy_listing", "1", cspr(100))
+        });
+        assert_revert(result, ApiError::InvalidArgument);
+        assert_eq!(fixture.balance(buyer), before - fee);
+
+        // a well formed hash that isn't the market fails in the engine, still rolling back the transfer
+        let unknown = ContractHash::new([0xee; 32]).to_formatted_string();
+        let before = fixture.balance(buyer);
+        let (result, fee) = fixture.fee_of(|fixture| fixture.pay_with(buyer, unknown, "buy_listing", "1", cspr(100)));
+        assert!(result.is_err());
+        assert_eq!(fixture.balance(buyer), before - fee);
     }
 }
 
@@ -22,7 +22,9 @@ const {
   MARKET_WASM_PATH,
   MARKET_MASTER_KEY_PAIR_PATH,
   MARKET_CONTRACT_NAME,
-  MARKET_INSTALL_PAYMENT_AMOUNT
+  MARKET_INSTALL_PAYMENT_AMOUNT,
+  MARKET_MAX_OFFERS_PER_TOKEN,
+  MARKET_MIN_OFFER_AMOUNT
 } = process.env;
 
 export const getBinary = (pathToBinary: string) => {
@@ -39,7 +41,9 @@ const test = async () => {
   const contract = new Contracts.Contract(client);
 
   const runtimeArgs = RuntimeArgs.fromMap({
-    contract_name: CLValueBuilder.string(MARKET_CONTRACT_NAME!)
+    contract_name: CLValueBuilder.string(MARKET_CONTRACT_NAME!),
+    max_offers_per_token: CLValueBuilder.u32(MARKET_MAX_OFFERS_PER_TOKEN!),
+    min_offer_amount: CLValueBuilder.u512(MARKET_MIN_OFFER_AMOUNT!)
   });
 
   const installDeployHash = await contract.install(getBinary(MARKET_WASM_PATH!), runtimeArgs, MARKET_INSTALL_PAYMENT_AMOUNT!, KEYS.publicKey, CHAIN_NAME!, [KEYS]);
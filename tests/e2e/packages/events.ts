@@ -1,6 +1,8 @@
 import { parseTokenMeta, sleep, getDeploy, getAccountInfo, getAccountNamedKeyValue } from "../utils";
+import { BigNumber } from '@ethersproject/bignumber';
 
 import {
+  CasperServiceByJsonRPC,
   CLValueBuilder,
   CLValueParsers,
   CLValue,
@@ -53,4 +55,107 @@ export const EventParser = (
   }
 
   return null;
-};
\ No newline at end of file
+};
+
+// market events follow the Casper Event Standard: each one is stored as `List<U8>` bytes in the
+// contract's `__events` dictionary keyed by index, with `__events_length` as the counter
+const EVENT_SCHEMAS: { [name: string]: [string, string][] } = {
+  ListingCreated: [["seller", "Key"], ["token_contract", "Hash"], ["token_id", "String"], ["price", "U512"]],
+  ListingPurchased: [["seller", "Key"], ["buyer", "Key"], ["token_contract", "Hash"], ["token_id", "String"], ["price", "U512"]],
+  ListingCanceled: [["token_contract", "Hash"], ["token_id", "String"]],
+  OfferCreated: [["buyer", "Key"], ["token_contract", "Hash"], ["token_id", "String"], ["price", "U512"]],
+  OfferWithdraw: [["buyer", "Key"], ["token_contract", "Hash"], ["token_id", "String"]],
+  OfferAccepted: [["seller", "Key"], ["buyer", "Key"], ["token_contract", "Hash"], ["token_id", "String"], ["price", "U512"]]
+};
+
+const KEY_PREFIXES = ["account-hash-", "hash-", "uref-"];
+const UREF_TAG = 2;
+
+export const decodeMarketEvent = (bytes: Uint8Array) => {
+  let offset = 0;
+  const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+  const toHex = (data: Uint8Array) => Array.from(data).map((b) => ("0" + b.toString(16)).slice(-2)).join("");
+
+  const readString = () => {
+    const length = view.getUint32(offset, true);
+    offset += 4;
+    const value = new TextDecoder().decode(bytes.slice(offset, offset + length));
+    offset += length;
+    return value;
+  };
+  const readHash = () => {
+    const value = toHex(bytes.slice(offset, offset + 32));
+    offset += 32;
+    return value;
+  };
+  const readKey = () => {
+    const tag = bytes[offset];
+    offset += 1;
+    if (tag === UREF_TAG) {
+      // a URef is its address followed by an access rights byte
+      const address = readHash();
+      const accessRights = ("00" + bytes[offset].toString(8)).slice(-3);
+      offset += 1;
+      return `${KEY_PREFIXES[tag]}${address}-${accessRights}`;
+    }
+    return `${KEY_PREFIXES[tag] || `key-${tag}-`}${readHash()}`;
+  };
+  const readU512 = () => {
+    const length = bytes[offset];
+    offset += 1;
+    let value = BigNumber.from(0);
+    for (let i = length - 1; i >= 0; i--) {
+      value = value.mul(256).add(bytes[offset + i]);
+    }
+    offset += length;
+    return value.toString();
+  };
+
+  const name = readString().replace(/^event_/, "");
+  const data: { [field: string]: string } = {};
+  for (const [field, type] of EVENT_SCHEMAS[name] || []) {
+    switch (type) {
+      case "Key": data[field] = readKey(); break;
+      case "Hash": data[field] = `contract-${readHash()}`; break;
+      case "String": data[field] = readString(); break;
+      case "U512": data[field] = readU512(); break;
+    }
+  }
+
+  return { name, data };
+};
+
+export const getMarketEventsLength = async (
+  nodeAddress: string,
+  marketContractHash: string
+) => {
+  const client = new CasperServiceByJsonRPC(nodeAddress);
+  const stateRootHash = await client.getStateRootHash();
+  const length = await client.getBlockState(stateRootHash, marketContractHash, ["__events_length"]);
+  return Number(length.CLValue!.value().toString());
+};
+
+export const getMarketEvents = async (
+  nodeAddress: string,
+  marketContractHash: string,
+  fromIndex: number = 0
+) => {
+  const client = new CasperServiceByJsonRPC(nodeAddress);
+  const stateRootHash = await client.getStateRootHash();
+  const length = await getMarketEventsLength(nodeAddress, marketContractHash);
+
+  const events = [];
+  for (let index = fromIndex; index < length; index++) {
+    const item = await client.getDictionaryItemByName(
+      stateRootHash,
+      marketContractHash,
+      "__events",
+      index.toString()
+    );
+    // a `List<U8>` serializes as its length followed by the bytes themselves
+    const bytes = CLValueParsers.toBytes(item.CLValue!).unwrap().slice(4);
+    events.push({ index, ...decodeMarketEvent(bytes) });
+  }
+
+  return events;
+};
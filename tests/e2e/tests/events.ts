@@ -1,6 +1,6 @@
 import { TestConfig } from "../packages/configure"
 import { CEP47Events } from "casper-cep47-js-client";
-import { EventParser } from "../packages/events"
+import { EventParser, getMarketEvents } from "../packages/events"
 import { sleep } from "../utils"
 
 import {
@@ -9,12 +9,12 @@ import {
 } from "casper-js-sdk";
 
 export enum MarketEvents {
-  ListingCreated= "market_listing_created",
-  ListingPurchased = "market_listing_purchased",
-  ListingCanceled = "market_listing_canceled",
-  OfferCreated = "market_offer_created",
-  OfferWithdraw = "market_offer_withdraw",
-  OfferAccepted = "market_offer_accepted"
+  ListingCreated= "ListingCreated",
+  ListingPurchased = "ListingPurchased",
+  ListingCanceled = "ListingCanceled",
+  OfferCreated = "OfferCreated",
+  OfferWithdraw = "OfferWithdraw",
+  OfferAccepted = "OfferAccepted"
 };
 
 
@@ -39,6 +39,26 @@ const watchEvents = async (eventNames: string[], contractPackageHash: string) =>
   es.start();  
 }
 
+// market events are read back from the contract's event log, starting after the last one seen
+const watchMarketEvents = async (eventNames: string[], marketContractHash: string) => {
+  const es = new EventStream(config.eventSteamAddress);
+  let nextIndex = 0;
+
+  es.subscribe(EventName.DeployProcessed, async () => {
+    const newEvents = await getMarketEvents(config.nodeAddress, marketContractHash, nextIndex);
+    nextIndex += newEvents.length;
+
+    const parsedEvents = newEvents.filter((event) => eventNames.includes(event.name));
+    if (parsedEvents.length) {
+      console.log("*** EVENT ***");
+      console.log(parsedEvents);
+      console.log("*** ***");
+    }
+  });
+
+  es.start();
+}
+
 const events = async () => {
   await config.setup();
 
@@ -51,14 +71,14 @@ const events = async () => {
       ], 
       config.nftContractPackageHash);
 
-  watchEvents([
+  watchMarketEvents([
         MarketEvents.ListingCreated,
         MarketEvents.ListingCanceled,
         MarketEvents.ListingPurchased,
         MarketEvents.OfferCreated,
         MarketEvents.OfferWithdraw
       ], 
-      config.marketContractPackageHash);
+      config.marketContractHash);
 }
 
 events();
\ No newline at end of file